@@ -0,0 +1,560 @@
+//! Reading and writing BGZF, the blocked gzip format written by `bgzip`.
+//!
+//! A BGZF file is a series of gzip members of at most 64 KiB each, so a
+//! reader can start decompressing at any block. Positions are given as
+//! *virtual offsets*: the compressed offset of a block shifted left by 16
+//! bits, plus an offset within its uncompressed data. A [`GziIndex`], as
+//! written by `bgzip -i`, maps uncompressed offsets to blocks so that
+//! [`Reader`] can also seek to plain uncompressed offsets.
+//!
+//! ```
+//! use std::io::{Cursor, Read, Seek, SeekFrom};
+//! use dna::bgzf::{Reader, Writer};
+//!
+//! let mut writer = Writer::new(Vec::new());
+//! std::io::Write::write_all(&mut writer, b">chr1\nACGTACGT\n").unwrap();
+//! let compressed = writer.finish().unwrap();
+//!
+//! let mut reader = Reader::new(Cursor::new(compressed));
+//! reader.seek(SeekFrom::Start(6)).unwrap();
+//! let mut seq = String::new();
+//! reader.read_to_string(&mut seq).unwrap();
+//! assert_eq!(seq, "ACGTACGT\n");
+//! ```
+
+use std::{
+    convert::TryFrom,
+    io::{self, BufRead, Read, Seek, SeekFrom, Write},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression, Crc};
+
+// gzip magic, deflate, and the FEXTRA flag every BGZF block sets
+const MAGIC: [u8; 4] = [0x1f, 0x8b, 8, 4];
+// the fixed gzip header fields up to and including XLEN
+const HEADER_LEN: usize = 12;
+// CRC32 and ISIZE
+const TRAILER_LEN: usize = 8;
+// the most uncompressed data `bgzip` puts in one block, leaving room for
+// incompressible input to still fit in 64 KiB
+const MAX_BLOCK_DATA: usize = 0xff00;
+// the empty block that marks the end of a BGZF file
+const EOF_BLOCK: [u8; 28] = [
+    0x1f, 0x8b, 8, 4, 0, 0, 0, 0, 0, 0xff, 6, 0, b'B', b'C', 2, 0, 0x1b, 0, 3, 0, 0, 0, 0, 0, 0, 0,
+    0, 0,
+];
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid BGZF: {}", msg))
+}
+
+/// Returns `true` if `header` starts with the header of a BGZF block.
+pub(crate) fn is_bgzf_header(header: &[u8]) -> bool {
+    header.len() >= 16 && header[..4] == MAGIC && &header[12..14] == b"BC"
+}
+
+/// Reads the header of the next block, returning the total size of the
+/// block and the size of its header, or `None` at the end of the input.
+fn read_header<R: Read>(inner: &mut R) -> io::Result<Option<(usize, usize)>> {
+    let mut header = [0u8; HEADER_LEN];
+    let mut filled = 0;
+    while filled < HEADER_LEN {
+        match inner.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(invalid_data("truncated block header")),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    if header[..4] != MAGIC {
+        return Err(invalid_data("block does not start with a gzip header"));
+    }
+    let xlen = usize::from(u16::from_le_bytes([header[10], header[11]]));
+    let mut extra = vec![0u8; xlen];
+    inner.read_exact(&mut extra)?;
+    // look through the extra subfields for the BC field holding the size
+    let mut fields = &extra[..];
+    while fields.len() >= 4 {
+        let len = usize::from(u16::from_le_bytes([fields[2], fields[3]]));
+        let data = fields
+            .get(4..4 + len)
+            .ok_or_else(|| invalid_data("truncated extra field"))?;
+        if &fields[..2] == b"BC" && len == 2 {
+            let size = usize::from(u16::from_le_bytes([data[0], data[1]])) + 1;
+            if size < HEADER_LEN + xlen + TRAILER_LEN {
+                return Err(invalid_data("block size too small"));
+            }
+            return Ok(Some((size, HEADER_LEN + xlen)));
+        }
+        fields = &fields[4 + len..];
+    }
+    Err(invalid_data("block has no BC field"))
+}
+
+/// An index of the blocks of a BGZF file, mapping the compressed offset of
+/// every block to the uncompressed offset its data starts at.
+///
+/// This is the `.gzi` index `bgzip -i` and `samtools faidx` write next to
+/// compressed references.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GziIndex {
+    // (compressed, uncompressed) offsets of every block in file order,
+    // starting with the first block at (0, 0)
+    entries: Vec<(u64, u64)>,
+}
+
+impl GziIndex {
+    /// Builds the index by walking the block headers of `inner` from its
+    /// start. Only the headers and trailers are read; nothing is
+    /// decompressed.
+    pub fn build<R: Read + Seek>(mut inner: R) -> io::Result<GziIndex> {
+        inner.seek(SeekFrom::Start(0))?;
+        let mut entries = Vec::new();
+        let (mut coffset, mut uoffset) = (0u64, 0u64);
+        while let Some((size, header_len)) = read_header(&mut inner)? {
+            let skip = size - header_len - TRAILER_LEN;
+            inner.seek(SeekFrom::Current(skip as i64 + 4))?;
+            let mut isize = [0u8; 4];
+            inner.read_exact(&mut isize)?;
+            entries.push((coffset, uoffset));
+            coffset += size as u64;
+            uoffset += u64::from(u32::from_le_bytes(isize));
+        }
+        if entries.is_empty() {
+            entries.push((0, 0));
+        }
+        Ok(GziIndex { entries })
+    }
+
+    /// Reads an index in the `.gzi` format: a little endian count followed
+    /// by that many pairs of compressed and uncompressed offsets, leaving
+    /// out the first block.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<GziIndex> {
+        let mut word = [0u8; 8];
+        reader.read_exact(&mut word)?;
+        let count = u64::from_le_bytes(word);
+        let mut entries = vec![(0, 0)];
+        for _ in 0..count {
+            reader.read_exact(&mut word)?;
+            let coffset = u64::from_le_bytes(word);
+            reader.read_exact(&mut word)?;
+            let uoffset = u64::from_le_bytes(word);
+            let &(last_c, last_u) = entries.last().expect("first block");
+            if coffset < last_c || uoffset < last_u {
+                return Err(invalid_data("index entries out of order"));
+            }
+            entries.push((coffset, uoffset));
+        }
+        Ok(GziIndex { entries })
+    }
+
+    /// Writes the index in the `.gzi` format.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let rest = &self.entries[1..];
+        writer.write_all(&(rest.len() as u64).to_le_bytes())?;
+        for &(coffset, uoffset) in rest {
+            writer.write_all(&coffset.to_le_bytes())?;
+            writer.write_all(&uoffset.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the compressed and uncompressed offsets of the block holding
+    /// uncompressed offset `pos`.
+    fn block_for(&self, pos: u64) -> (u64, u64) {
+        let idx = self.entries.partition_point(|&(_, uoffset)| uoffset <= pos);
+        self.entries[idx.saturating_sub(1)]
+    }
+
+    /// Returns the uncompressed offset of the block at compressed offset
+    /// `coffset`, if it is in the index.
+    fn uncompressed_offset(&self, coffset: u64) -> Option<u64> {
+        self.entries
+            .binary_search_by_key(&coffset, |&(c, _)| c)
+            .ok()
+            .map(|idx| self.entries[idx].1)
+    }
+}
+
+/// A reader that decompresses BGZF.
+///
+/// It reads sequentially like any gzip decoder, and with a seekable source
+/// it can also jump to a virtual offset with [`Reader::seek_virtual`] or to
+/// an uncompressed offset through [`Seek`]. Seeking by uncompressed offset
+/// uses a [`GziIndex`], which is built from the block headers the first
+/// time it is needed unless one is supplied with [`Reader::with_index`].
+/// Since the index does not record the size of the last block, seeking
+/// from the end decompresses the last block to find it.
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+    // decompressed data of the current block and the read position in it
+    block: Vec<u8>,
+    pos: usize,
+    // compressed offsets of the current block and of the one after it
+    block_offset: u64,
+    next_offset: u64,
+    cdata: Vec<u8>,
+    index: Option<GziIndex>,
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a reader over `inner`, which must be positioned at the start
+    /// of a BGZF file.
+    pub fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            block: Vec::new(),
+            pos: 0,
+            block_offset: 0,
+            next_offset: 0,
+            cdata: Vec::new(),
+            index: None,
+        }
+    }
+
+    /// Sets the block index used for seeking by uncompressed offset,
+    /// replacing any built so far.
+    pub fn set_index(&mut self, index: GziIndex) {
+        self.index = Some(index);
+    }
+
+    /// Returns the virtual offset of the next byte to be read.
+    pub fn virtual_position(&self) -> u64 {
+        if self.pos == self.block.len() && !self.block.is_empty() {
+            self.next_offset << 16
+        } else {
+            (self.block_offset << 16) | self.pos as u64
+        }
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decompresses the next block, returning `false` at the end of the
+    /// input.
+    fn read_block(&mut self) -> io::Result<bool> {
+        let (size, header_len) = match read_header(&mut self.inner)? {
+            Some(sizes) => sizes,
+            None => return Ok(false),
+        };
+        self.cdata.resize(size - header_len, 0);
+        self.inner.read_exact(&mut self.cdata)?;
+        let (cdata, trailer) = self.cdata.split_at(size - header_len - TRAILER_LEN);
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let isize = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+
+        self.block.clear();
+        self.block.reserve(isize as usize);
+        DeflateDecoder::new(cdata).read_to_end(&mut self.block)?;
+        let mut check = Crc::new();
+        check.update(&self.block);
+        if self.block.len() != isize as usize || check.sum() != crc {
+            return Err(invalid_data("block checksum mismatch"));
+        }
+        self.pos = 0;
+        self.block_offset = self.next_offset;
+        self.next_offset += size as u64;
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// Creates a reader over `inner` that seeks with an existing `index`.
+    pub fn with_index(inner: R, index: GziIndex) -> Reader<R> {
+        Reader {
+            index: Some(index),
+            ..Reader::new(inner)
+        }
+    }
+
+    /// Returns the block index, building it first if needed.
+    pub fn index(&mut self) -> io::Result<&GziIndex> {
+        if self.index.is_none() {
+            let index = GziIndex::build(&mut self.inner)?;
+            // building moved the source, so reload the current block
+            self.inner.seek(SeekFrom::Start(self.block_offset))?;
+            self.next_offset = self.block_offset;
+            let pos = self.pos;
+            if !self.block.is_empty() {
+                self.read_block()?;
+            }
+            self.pos = pos;
+            self.index = Some(index);
+        }
+        Ok(self.index.as_ref().expect("index just built"))
+    }
+
+    /// Returns the length of the uncompressed data, decompressing the last
+    /// block in the index and any after it to find it.
+    fn uncompressed_len(&mut self) -> io::Result<u64> {
+        let (coffset, mut len) = *self.index()?.entries.last().expect("first block");
+        self.inner.seek(SeekFrom::Start(coffset))?;
+        self.next_offset = coffset;
+        while self.read_block()? {
+            len += self.block.len() as u64;
+        }
+        Ok(len)
+    }
+
+    /// Moves to virtual offset `offset`.
+    pub fn seek_virtual(&mut self, offset: u64) -> io::Result<()> {
+        let (coffset, within) = (offset >> 16, (offset & 0xffff) as usize);
+        self.inner.seek(SeekFrom::Start(coffset))?;
+        self.next_offset = coffset;
+        self.block.clear();
+        self.read_block()?;
+        if within > self.block.len() {
+            return Err(invalid_data("virtual offset past the end of its block"));
+        }
+        self.pos = within;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+        Ok(count)
+    }
+}
+
+impl<R: Read> BufRead for Reader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // skip over empty blocks, such as the end of file marker
+        while self.pos == self.block.len() {
+            if !self.read_block()? {
+                break;
+            }
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.block.len());
+    }
+}
+
+/// Applies a signed seek `delta` to `base`, or returns `None` if the result
+/// would be negative.
+fn offset_by(base: u64, delta: i64) -> Option<u64> {
+    if delta < 0 {
+        base.checked_sub(delta.unsigned_abs())
+    } else {
+        base.checked_add(delta as u64)
+    }
+}
+
+/// Seeks by uncompressed offset.
+impl<R: Read + Seek> Seek for Reader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(delta) => {
+                let block_offset = self.block_offset;
+                let start = self
+                    .index()?
+                    .uncompressed_offset(block_offset)
+                    .ok_or_else(|| invalid_data("current block is not in the index"))?;
+                offset_by(start + self.pos as u64, delta)
+            }
+            SeekFrom::End(delta) => offset_by(self.uncompressed_len()?, delta),
+        };
+        let target = target.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative offset")
+        })?;
+        let (coffset, uoffset) = self.index()?.block_for(target);
+        self.seek_virtual(coffset << 16)?;
+        let mut remaining = target - uoffset;
+        loop {
+            let available = (self.block.len() - self.pos) as u64;
+            if remaining <= available {
+                self.pos += remaining as usize;
+                break;
+            }
+            remaining -= available;
+            self.pos = self.block.len();
+            if !self.read_block()? {
+                // past the end, like a file
+                break;
+            }
+        }
+        Ok(target)
+    }
+}
+
+/// A writer that compresses into BGZF blocks.
+///
+/// Call [`Writer::finish`] when done to write the final block and the end
+/// of file marker; dropping the writer without it leaves a truncated file.
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    compressed: Vec<u8>,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a writer over `inner`.
+    pub fn new(inner: W) -> Writer<W> {
+        Writer {
+            inner,
+            buf: Vec::with_capacity(MAX_BLOCK_DATA),
+            compressed: Vec::new(),
+        }
+    }
+
+    /// Compresses the buffered data into one block.
+    fn write_block(&mut self) -> io::Result<()> {
+        let mut encoder =
+            DeflateEncoder::new(std::mem::take(&mut self.compressed), Compression::default());
+        encoder.write_all(&self.buf)?;
+        self.compressed = encoder.finish()?;
+        let size = HEADER_LEN + 6 + self.compressed.len() + TRAILER_LEN;
+        let bsize = u16::try_from(size - 1).map_err(|_| invalid_data("block too large"))?;
+        let mut header = [0u8; HEADER_LEN + 6];
+        header[..4].copy_from_slice(&MAGIC);
+        header[9] = 0xff;
+        header[10] = 6;
+        header[12..16].copy_from_slice(&[b'B', b'C', 2, 0]);
+        header[16..].copy_from_slice(&bsize.to_le_bytes());
+        let mut crc = Crc::new();
+        crc.update(&self.buf);
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&self.compressed)?;
+        self.inner.write_all(&crc.sum().to_le_bytes())?;
+        self.inner
+            .write_all(&(self.buf.len() as u32).to_le_bytes())?;
+        self.compressed.clear();
+        self.buf.clear();
+        Ok(())
+    }
+
+    /// Writes any buffered data and the end of file marker, and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.buf.is_empty() {
+            self.write_block()?;
+        }
+        self.inner.write_all(&EOF_BLOCK)?;
+        self.inner.flush()?;
+        // `Writer` has no destructor, so the fields can be moved out
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let count = data.len().min(MAX_BLOCK_DATA - self.buf.len());
+        self.buf.extend_from_slice(&data[..count]);
+        if self.buf.len() == MAX_BLOCK_DATA {
+            self.write_block()?;
+        }
+        Ok(count)
+    }
+
+    /// Ends the current block, so everything written so far can be read
+    /// back.
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.write_block()?;
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sample() -> Vec<u8> {
+        (0..200_000u32)
+            .map(|i| b"ACGT\n"[(i * 7 % 5) as usize])
+            .collect()
+    }
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut writer = Writer::new(Vec::new());
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap()
+    }
+
+    // Test to check that multi-block data decompresses back unchanged and
+    // that the EOF marker is written
+    #[test]
+    fn test_round_trip() {
+        let data = sample();
+        let compressed = compress(&data);
+        assert!(compressed.ends_with(&EOF_BLOCK));
+        assert!(is_bgzf_header(&compressed));
+        let mut out = Vec::new();
+        Reader::new(&compressed[..]).read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+        // gzip readers see the blocks as concatenated members
+        let mut out = Vec::new();
+        flate2::read::MultiGzDecoder::new(&compressed[..])
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+        let mut empty = Vec::new();
+        Reader::new(&compress(b"")[..])
+            .read_to_end(&mut empty)
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    // Test to check seeking by virtual and uncompressed offsets, with a
+    // built index and one read back from its `.gzi` form
+    #[test]
+    fn test_seek() {
+        let data = sample();
+        let compressed = compress(&data);
+        let index = GziIndex::build(Cursor::new(&compressed)).unwrap();
+        assert_eq!(index.entries.len(), 5);
+        let mut gzi = Vec::new();
+        index.write_to(&mut gzi).unwrap();
+        assert_eq!(gzi.len(), 8 + 4 * 16);
+        assert_eq!(GziIndex::read_from(&gzi[..]).unwrap(), index);
+
+        let mut reader = Reader::with_index(Cursor::new(&compressed), index.clone());
+        let mut lazy = Reader::new(Cursor::new(&compressed));
+        for &target in &[0u64, 1, 65279, 65280, 65281, 150_000, 199_990] {
+            for reader in [&mut reader, &mut lazy] {
+                assert_eq!(reader.seek(SeekFrom::Start(target)).unwrap(), target);
+                let mut buf = [0u8; 10];
+                let count = reader.read(&mut buf).unwrap();
+                let expected = &data[target as usize..];
+                assert_eq!(&buf[..count], &expected[..count.min(expected.len())]);
+                assert_eq!(
+                    reader.seek(SeekFrom::Current(-(count as i64))).unwrap(),
+                    target
+                );
+            }
+        }
+
+        // remember a virtual offset while reading and come back to it
+        let mut reader = Reader::new(Cursor::new(&compressed));
+        let mut skipped = vec![0u8; 100_000];
+        reader.read_exact(&mut skipped).unwrap();
+        let offset = reader.virtual_position();
+        let mut expected = vec![0u8; 1000];
+        reader.read_exact(&mut expected).unwrap();
+        reader.seek_virtual(0).unwrap();
+        reader.seek_virtual(offset).unwrap();
+        let mut again = vec![0u8; 1000];
+        reader.read_exact(&mut again).unwrap();
+        assert_eq!(again, expected);
+        assert_eq!(&again[..], &data[100_000..101_000]);
+        assert_eq!(reader.seek(SeekFrom::End(-5)).unwrap(), 199_995);
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, &data[199_995..]);
+    }
+}
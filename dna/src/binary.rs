@@ -0,0 +1,262 @@
+//! A compact, versioned binary format for [`PackedDna`].
+
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    packed::{reverse_slots, words_for, NUCS_PER_WORD},
+    PackedDna,
+};
+
+const MAGIC: [u8; 4] = *b"PDNA";
+const VERSION: u8 = 1;
+pub(crate) const LOW_BITS_FIRST: u8 = 0;
+const HIGH_BITS_FIRST: u8 = 1;
+pub(crate) const HEADER_LEN: usize = 16;
+// words read at a time, so a corrupt length fails on truncation instead of
+// allocating all of it up front
+const CHUNK_WORDS: usize = 8192;
+
+/// An error that can occur while reading a [`PackedDna`] with
+/// [`PackedDna::read_from`].
+#[derive(Debug, thiserror::Error)]
+pub enum ReadDnaError {
+    /// Reading from the underlying source failed.
+    #[error("failed to read packed DNA: {0}")]
+    Io(#[source] io::Error),
+    /// The input ended before the checksum.
+    #[error("packed DNA is truncated")]
+    Truncated,
+    /// The input does not start with the `PDNA` magic.
+    #[error("not packed DNA")]
+    BadMagic,
+    /// The input was written in a version of the format this crate does not
+    /// know.
+    #[error("unsupported packed DNA version {0}")]
+    UnsupportedVersion(u8),
+    /// The bit order flag is neither of the known orders.
+    #[error("unknown bit order {0}")]
+    UnknownBitOrder(u8),
+    /// The sequence is too long to hold in memory on this target.
+    #[error("packed DNA of {0} nucleotides is too long")]
+    TooLong(u64),
+    /// The slots past the end of the sequence are not zero.
+    #[error("packed DNA has bits set past its end")]
+    InvalidPadding,
+    /// The checksum does not match the contents.
+    #[error("checksum mismatch: stored {stored:#010x}, computed {computed:#010x}")]
+    ChecksumMismatch {
+        /// The checksum stored in the input.
+        stored: u32,
+        /// The checksum of the contents as read.
+        computed: u32,
+    },
+}
+
+impl From<io::Error> for ReadDnaError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            ReadDnaError::Truncated
+        } else {
+            ReadDnaError::Io(err)
+        }
+    }
+}
+
+/// Checks the header of the binary format, returning the number of
+/// nucleotides and the bit order.
+pub(crate) fn parse_header(header: &[u8; HEADER_LEN]) -> Result<(usize, u8), ReadDnaError> {
+    if header[..4] != MAGIC {
+        return Err(ReadDnaError::BadMagic);
+    }
+    if header[4] != VERSION {
+        return Err(ReadDnaError::UnsupportedVersion(header[4]));
+    }
+    let bit_order = header[5];
+    if bit_order != LOW_BITS_FIRST && bit_order != HIGH_BITS_FIRST {
+        return Err(ReadDnaError::UnknownBitOrder(bit_order));
+    }
+    let mut len = [0u8; 8];
+    len.copy_from_slice(&header[8..]);
+    let len = u64::from_le_bytes(len);
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|&len| len.checked_add(NUCS_PER_WORD).is_some())
+        .ok_or(ReadDnaError::TooLong(len))?;
+    Ok((len, bit_order))
+}
+
+/// Checks that the slots past the first `len` nucleotides of `words` are
+/// zero, as [`PackedDna`] requires.
+pub(crate) fn check_padding(words: &[u64], len: usize) -> Result<(), ReadDnaError> {
+    let tail = len % NUCS_PER_WORD;
+    match words.last() {
+        Some(&last) if tail != 0 && last >> (tail * 2) != 0 => Err(ReadDnaError::InvalidPadding),
+        _ => Ok(()),
+    }
+}
+
+impl PackedDna {
+    /// Writes the sequence in a compact, versioned binary format, which
+    /// [`PackedDna::read_from`] reads back.
+    ///
+    /// All integers are little endian. The output is a 16 byte header, the packed
+    /// words, and a checksum:
+    ///
+    /// | offset | size | field                                                  |
+    /// |--------|------|--------------------------------------------------------|
+    /// | 0      | 4    | magic, `PDNA`                                          |
+    /// | 4      | 1    | format version, currently 1                            |
+    /// | 5      | 1    | bit order: 0 if the first nucleotide of a word is in its low bits, 1 if in its high bits |
+    /// | 6      | 2    | reserved, zero                                         |
+    /// | 8      | 8    | number of nucleotides                                  |
+    /// | 16     | 8 × words | the nucleotides, 32 per `u64`, A = 0, C = 1, G = 2, T = 3, unused slots zero |
+    /// | end    | 4    | CRC-32 of everything before it                         |
+    ///
+    /// The words are always written low bits first, which is their layout in
+    /// memory, so writing is a straight copy.
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use dna::PackedDna;
+    ///
+    /// let dna = PackedDna::from_str("ACGTTGCA").unwrap();
+    /// let mut bytes = Vec::new();
+    /// dna.write_to(&mut bytes).unwrap();
+    /// assert_eq!(bytes.len(), 16 + 8 + 4);
+    /// assert_eq!(PackedDna::read_from(&mut &bytes[..]).unwrap(), dna);
+    /// ```
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut hasher = crc32fast::Hasher::new();
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4] = VERSION;
+        header[5] = LOW_BITS_FIRST;
+        header[8..].copy_from_slice(&(self.len() as u64).to_le_bytes());
+        hasher.update(&header);
+        writer.write_all(&header)?;
+
+        let mut buf = Vec::with_capacity(CHUNK_WORDS.min(self.words().len()) * 8);
+        for chunk in self.words().chunks(CHUNK_WORDS) {
+            buf.clear();
+            buf.extend(chunk.iter().flat_map(|word| word.to_le_bytes()));
+            hasher.update(&buf);
+            writer.write_all(&buf)?;
+        }
+        writer.write_all(&hasher.finalize().to_le_bytes())
+    }
+
+    /// Reads a sequence written by [`PackedDna::write_to`], checking the
+    /// header and the checksum.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<PackedDna, ReadDnaError> {
+        let mut hasher = crc32fast::Hasher::new();
+        let mut header = [0u8; HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        hasher.update(&header);
+        let (len, bit_order) = parse_header(&header)?;
+
+        let total = words_for(len);
+        let mut words = Vec::with_capacity(total.min(CHUNK_WORDS));
+        let mut buf = vec![0u8; total.min(CHUNK_WORDS) * 8];
+        while words.len() < total {
+            let count = (total - words.len()).min(CHUNK_WORDS);
+            let bytes = &mut buf[..count * 8];
+            reader.read_exact(bytes)?;
+            hasher.update(bytes);
+            words.extend(bytes.chunks(8).map(|chunk| {
+                let mut word = [0u8; 8];
+                word.copy_from_slice(chunk);
+                let word = u64::from_le_bytes(word);
+                if bit_order == HIGH_BITS_FIRST {
+                    reverse_slots(word)
+                } else {
+                    word
+                }
+            }));
+        }
+
+        let mut stored = [0u8; 4];
+        reader.read_exact(&mut stored)?;
+        let stored = u32::from_le_bytes(stored);
+        let computed = hasher.finalize();
+        if stored != computed {
+            return Err(ReadDnaError::ChecksumMismatch { stored, computed });
+        }
+        check_padding(&words, len)?;
+        Ok(PackedDna::from_raw(words, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+    use std::str::FromStr;
+
+    // Test to check that sequences of several lengths read back unchanged,
+    // including one spanning more than a chunk and one in high bits first
+    // order
+    #[test]
+    fn test_round_trip() {
+        let long = "ACGTTGCAA".repeat(CHUNK_WORDS * 4);
+        for text in [
+            "",
+            "A",
+            "ACGTACGTACGTACGTACGTACGTACGTACGT",
+            "GATTACA",
+            &long,
+        ] {
+            let dna = ParseOptions::new().allow_empty(true).parse(text).unwrap();
+            let mut bytes = Vec::new();
+            dna.write_to(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), 16 + words_for(dna.len()) * 8 + 4);
+            assert_eq!(PackedDna::read_from(&mut &bytes[..]).unwrap(), dna);
+        }
+
+        // the same words with their slots reversed, and the header flag and
+        // checksum to match
+        let dna = PackedDna::from_str("GATTACA").unwrap();
+        let mut bytes = Vec::new();
+        dna.write_to(&mut bytes).unwrap();
+        bytes[5] = HIGH_BITS_FIRST;
+        let reversed = reverse_slots(dna.words()[0]);
+        bytes[16..24].copy_from_slice(&reversed.to_le_bytes());
+        let crc = crc32fast::hash(&bytes[..24]);
+        bytes[24..].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(PackedDna::read_from(&mut &bytes[..]).unwrap(), dna);
+    }
+
+    // Test to check the errors for truncated, foreign, newer and corrupted
+    // input
+    #[test]
+    fn test_read_errors() {
+        let dna = PackedDna::from_str("ACGTACGTACGTACGTACGTACGTACGTACGTAC").unwrap();
+        let mut bytes = Vec::new();
+        dna.write_to(&mut bytes).unwrap();
+        let read = |bytes: &[u8]| PackedDna::read_from(&mut &bytes[..]);
+
+        for len in [0, 10, 16, 30, bytes.len() - 1] {
+            assert!(matches!(read(&bytes[..len]), Err(ReadDnaError::Truncated)));
+        }
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert!(matches!(read(&corrupt), Err(ReadDnaError::BadMagic)));
+        let mut corrupt = bytes.clone();
+        corrupt[4] = 2;
+        assert!(matches!(
+            read(&corrupt),
+            Err(ReadDnaError::UnsupportedVersion(2))
+        ));
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 1;
+        assert!(matches!(
+            read(&corrupt),
+            Err(ReadDnaError::ChecksumMismatch { .. })
+        ));
+        let mut corrupt = bytes;
+        corrupt[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(read(&corrupt), Err(ReadDnaError::TooLong(_))));
+    }
+}
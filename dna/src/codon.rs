@@ -0,0 +1,499 @@
+//! Codons, the NCBI genetic codes and translation of packed sequences into
+//! protein.
+
+use std::fmt::{self, Display};
+
+use crate::{Nuc, PackedDna, PackedDnaSlice};
+
+/// A codon: three consecutive nucleotides read as one unit of translation.
+///
+/// ```
+/// use dna::{Codon, GeneticCode, Nuc};
+///
+/// let codon = Codon::new(Nuc::T, Nuc::G, Nuc::A);
+/// assert_eq!(codon.to_string(), "TGA");
+/// assert!(GeneticCode::standard().is_stop(codon));
+/// assert_eq!(GeneticCode::from_id(2).unwrap().amino_acid(codon), 'W');
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Codon([Nuc; 3]);
+
+impl Codon {
+    /// Creates a codon from its three nucleotides, in reading order.
+    pub fn new(first: Nuc, second: Nuc, third: Nuc) -> Codon {
+        Codon([first, second, third])
+    }
+
+    /// Returns the three nucleotides of the codon, in reading order.
+    pub fn nucs(self) -> [Nuc; 3] {
+        self.0
+    }
+
+    /// Returns the codon read from the opposite strand.
+    pub fn reverse_complement(self) -> Codon {
+        let [first, second, third] = self.0;
+        Codon([third.complement(), second.complement(), first.complement()])
+    }
+
+    /// Returns the position of the codon in the NCBI tables, which list the
+    /// codons with each base running through T, C, A, G.
+    fn ncbi_index(self) -> usize {
+        fn rank(nuc: Nuc) -> usize {
+            match nuc {
+                Nuc::T => 0,
+                Nuc::C => 1,
+                Nuc::A => 2,
+                Nuc::G => 3,
+            }
+        }
+        let [first, second, third] = self.0;
+        rank(first) * 16 + rank(second) * 4 + rank(third)
+    }
+}
+
+impl From<[Nuc; 3]> for Codon {
+    fn from(nucs: [Nuc; 3]) -> Codon {
+        Codon(nucs)
+    }
+}
+
+impl Display for Codon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [first, second, third] = self.0;
+        write!(f, "{}{}{}", first, second, third)
+    }
+}
+
+/// A genetic code, mapping each of the 64 codons to an amino acid or a stop
+/// and marking which codons can start translation.
+///
+/// Every translation table published by the NCBI is available through
+/// [`GeneticCode::from_id`] under its NCBI number, and
+/// [`GeneticCode::all`] lists them. Amino acids are reported by their one
+/// letter code, with `*` for stop codons.
+///
+/// A few ciliate codes (27, 28 and 31) read some stop codons as amino acids
+/// depending on context; those codons translate to the amino acid here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneticCode {
+    id: u8,
+    name: &'static str,
+    // the amino acid and start flag of every codon, in NCBI order
+    amino_acids: &'static str,
+    starts: &'static str,
+}
+
+impl GeneticCode {
+    /// Returns the standard code (NCBI table 1).
+    pub fn standard() -> GeneticCode {
+        TABLES[0]
+    }
+
+    /// Returns the code with NCBI table number `id`, or `None` if there is
+    /// no such table.
+    pub fn from_id(id: u8) -> Option<GeneticCode> {
+        TABLES.iter().copied().find(|code| code.id == id)
+    }
+
+    /// Returns every NCBI code, ordered by table number.
+    pub fn all() -> &'static [GeneticCode] {
+        &TABLES
+    }
+
+    /// Returns the NCBI table number of the code.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Returns the NCBI name of the code.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the one letter code of the amino acid `codon` encodes, or
+    /// `*` if it is a stop codon.
+    pub fn amino_acid(&self, codon: Codon) -> char {
+        char::from(self.amino_acids.as_bytes()[codon.ncbi_index()])
+    }
+
+    /// Returns `true` if `codon` is a stop codon.
+    pub fn is_stop(&self, codon: Codon) -> bool {
+        self.amino_acid(codon) == '*'
+    }
+
+    /// Returns `true` if `codon` can start translation, including
+    /// alternative start codons.
+    pub fn is_start(&self, codon: Codon) -> bool {
+        self.starts.as_bytes()[codon.ncbi_index()] == b'M'
+    }
+}
+
+impl Display for GeneticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.id)
+    }
+}
+
+/// Options controlling how a sequence is translated into protein.
+///
+/// The defaults match [`PackedDna::translate`]: translation runs to the end
+/// of the sequence, stop codons are written as `*` and every codon is read
+/// with its usual meaning.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{GeneticCode, PackedDna, TranslateOptions};
+///
+/// let cds = PackedDna::from_str("TTGAAATGAGGG").unwrap();
+/// let code = GeneticCode::from_id(11).unwrap();
+/// assert_eq!(cds.translate(0, &code), "LK*G");
+/// let options = TranslateOptions::new().to_stop(true).alternative_starts(true);
+/// assert_eq!(options.translate(cds.as_slice(), 0, &code), "MK");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TranslateOptions {
+    to_stop: bool,
+    alternative_starts: bool,
+}
+
+impl TranslateOptions {
+    /// Creates the default translate options.
+    pub fn new() -> TranslateOptions {
+        TranslateOptions::default()
+    }
+
+    /// When enabled, translation ends before the first stop codon.
+    pub fn to_stop(mut self, yes: bool) -> TranslateOptions {
+        self.to_stop = yes;
+        self
+    }
+
+    /// When enabled, a first codon that the genetic code lists as a start
+    /// codon is translated as methionine, as it is by the ribosome, even if
+    /// it otherwise encodes another amino acid.
+    pub fn alternative_starts(mut self, yes: bool) -> TranslateOptions {
+        self.alternative_starts = yes;
+        self
+    }
+
+    /// Translates `seq` with `code`, reading codons from offset `frame`.
+    /// Trailing nucleotides that do not fill a codon are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not 0, 1 or 2.
+    pub fn translate(&self, seq: PackedDnaSlice<'_>, frame: usize, code: &GeneticCode) -> String {
+        let mut protein = String::with_capacity(seq.len() / 3);
+        for (idx, codon) in codons(seq, frame).enumerate() {
+            let amino_acid = if idx == 0 && self.alternative_starts && code.is_start(codon) {
+                'M'
+            } else {
+                code.amino_acid(codon)
+            };
+            if amino_acid == '*' && self.to_stop {
+                break;
+            }
+            protein.push(amino_acid);
+        }
+        protein
+    }
+}
+
+/// Returns the codons of `seq` read from offset `frame`, leaving out the
+/// trailing nucleotides that do not fill a codon.
+///
+/// # Panics
+///
+/// Panics if `frame` is not 0, 1 or 2.
+pub(crate) fn codons(seq: PackedDnaSlice<'_>, frame: usize) -> impl Iterator<Item = Codon> + '_ {
+    assert!(frame < 3, "reading frame {} is not 0, 1 or 2", frame);
+    let start = frame.min(seq.len());
+    let mut nucs = seq.slice(start..).iter();
+    let count = nucs.len() / 3;
+    (0..count).map(move |_| {
+        let mut next = || nucs.next().expect("codon within bounds");
+        Codon::new(next(), next(), next())
+    })
+}
+
+impl PackedDna {
+    /// Translates the sequence with `code`, reading codons from offset
+    /// `frame`. Stop codons are written as `*`; use [`TranslateOptions`] to
+    /// stop at the first one or to honour alternative start codons.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not 0, 1 or 2.
+    pub fn translate(&self, frame: usize, code: &GeneticCode) -> String {
+        TranslateOptions::new().translate(self.as_slice(), frame, code)
+    }
+}
+
+impl PackedDnaSlice<'_> {
+    /// Translates the viewed nucleotides, the same way
+    /// [`PackedDna::translate`] does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not 0, 1 or 2.
+    pub fn translate(self, frame: usize, code: &GeneticCode) -> String {
+        TranslateOptions::new().translate(self, frame, code)
+    }
+}
+
+// The NCBI translation tables, transcribed from
+// https://www.ncbi.nlm.nih.gov/Taxonomy/Utils/wprintgc.cgi. Codons run
+// through T, C, A, G for the first, second and third base in turn, and
+// the start row marks alternative starts with M.
+const TABLES: [GeneticCode; 26] = [
+    GeneticCode {
+        id: 1,
+        name: "Standard",
+        amino_acids: "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "---M---------------M---------------M----------------------------",
+    },
+    GeneticCode {
+        id: 2,
+        name: "Vertebrate Mitochondrial",
+        amino_acids: "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        starts: "--------------------------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 3,
+        name: "Yeast Mitochondrial",
+        amino_acids: "FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "----------------------------------MM----------------------------",
+    },
+    GeneticCode {
+        id: 4,
+        name: "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+        amino_acids: "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "--MM---------------M------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 5,
+        name: "Invertebrate Mitochondrial",
+        amino_acids: "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        starts: "---M----------------------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 6,
+        name: "Ciliate, Dasycladacean and Hexamita Nuclear",
+        amino_acids: "FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 9,
+        name: "Echinoderm and Flatworm Mitochondrial",
+        amino_acids: "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 10,
+        name: "Euplotid Nuclear",
+        amino_acids: "FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 11,
+        name: "Bacterial, Archaeal and Plant Plastid",
+        amino_acids: "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "---M---------------M------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 12,
+        name: "Alternative Yeast Nuclear",
+        amino_acids: "FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-------------------M---------------M----------------------------",
+    },
+    GeneticCode {
+        id: 13,
+        name: "Ascidian Mitochondrial",
+        amino_acids: "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        starts: "---M------------------------------MM---------------M------------",
+    },
+    GeneticCode {
+        id: 14,
+        name: "Alternative Flatworm Mitochondrial",
+        amino_acids: "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 16,
+        name: "Chlorophycean Mitochondrial",
+        amino_acids: "FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 21,
+        name: "Trematode Mitochondrial",
+        amino_acids: "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 22,
+        name: "Scenedesmus obliquus Mitochondrial",
+        amino_acids: "FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 23,
+        name: "Thraustochytrium Mitochondrial",
+        amino_acids: "FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "--------------------------------M--M---------------M------------",
+    },
+    GeneticCode {
+        id: 24,
+        name: "Rhabdopleuridae Mitochondrial",
+        amino_acids: "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        starts: "---M---------------M---------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 25,
+        name: "Candidate Division SR1 and Gracilibacteria",
+        amino_acids: "FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "---M-------------------------------M---------------M------------",
+    },
+    GeneticCode {
+        id: 26,
+        name: "Pachysolen tannophilus Nuclear",
+        amino_acids: "FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-------------------M---------------M----------------------------",
+    },
+    GeneticCode {
+        id: 27,
+        name: "Karyorelict Nuclear",
+        amino_acids: "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 28,
+        name: "Condylostoma Nuclear",
+        amino_acids: "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 29,
+        name: "Mesodinium Nuclear",
+        amino_acids: "FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 30,
+        name: "Peritrich Nuclear",
+        amino_acids: "FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 31,
+        name: "Blastocrithidia Nuclear",
+        amino_acids: "FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "-----------------------------------M----------------------------",
+    },
+    GeneticCode {
+        id: 32,
+        name: "Balanophoraceae Plastid",
+        amino_acids: "FFLLSSSSYY*WCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: "---M---------------M------------MMMM---------------M------------",
+    },
+    GeneticCode {
+        id: 33,
+        name: "Cephalodiscidae Mitochondrial",
+        amino_acids: "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        starts: "---M---------------M---------------M---------------M------------",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const CDS: &str = "ATGGCCATTGTAATGGGCCGCTGAAAGGGTGCCCGATAG";
+
+    // Test to check that every table is well formed and that the standard
+    // code has the usual stops and starts
+    #[test]
+    fn test_tables() {
+        for code in GeneticCode::all() {
+            assert_eq!(code.amino_acids.len(), 64, "table {}", code.id);
+            assert_eq!(code.starts.len(), 64, "table {}", code.id);
+            assert!(code.starts.bytes().all(|b| b == b'-' || b == b'M'));
+            assert_eq!(GeneticCode::from_id(code.id), Some(*code));
+        }
+        assert!(GeneticCode::all().windows(2).all(|w| w[0].id < w[1].id));
+        assert_eq!(GeneticCode::from_id(7), None);
+        assert_eq!(GeneticCode::all().len(), 26);
+        let balanophoraceae = GeneticCode::from_id(32).unwrap();
+        assert_eq!(balanophoraceae.name(), "Balanophoraceae Plastid");
+        assert_eq!(
+            balanophoraceae.amino_acid(Codon::new(Nuc::T, Nuc::A, Nuc::G)),
+            'W'
+        );
+        assert!(balanophoraceae.is_stop(Codon::new(Nuc::T, Nuc::A, Nuc::A)));
+
+        let standard = GeneticCode::standard();
+        let all_codons = || {
+            let nucs = [Nuc::A, Nuc::C, Nuc::G, Nuc::T];
+            let mut codons = Vec::new();
+            for &first in &nucs {
+                for &second in &nucs {
+                    for &third in &nucs {
+                        codons.push(Codon::new(first, second, third));
+                    }
+                }
+            }
+            codons
+        };
+        let stops: Vec<String> = all_codons()
+            .into_iter()
+            .filter(|&codon| standard.is_stop(codon))
+            .map(|codon| codon.to_string())
+            .collect();
+        assert_eq!(stops, ["TAA", "TAG", "TGA"]);
+        let starts: Vec<String> = all_codons()
+            .into_iter()
+            .filter(|&codon| standard.is_start(codon))
+            .map(|codon| codon.to_string())
+            .collect();
+        assert_eq!(starts, ["ATG", "CTG", "TTG"]);
+        assert_eq!(
+            Codon::new(Nuc::A, Nuc::T, Nuc::G).reverse_complement(),
+            Codon::new(Nuc::C, Nuc::A, Nuc::T)
+        );
+    }
+
+    // Test to check translation in every frame and with the options
+    #[test]
+    fn test_translate() {
+        let dna = PackedDna::from_str(CDS).unwrap();
+        let standard = GeneticCode::standard();
+        assert_eq!(dna.translate(0, &standard), "MAIVMGR*KGAR*");
+        assert_eq!(dna.translate(1, &standard), "WPL*WAAERVPD");
+        assert_eq!(dna.translate(2, &standard), "GHCNGPLKGCPI");
+        assert_eq!(dna.slice(3..9).translate(0, &standard), "AI");
+        assert_eq!(dna.slice(0..2).translate(2, &standard), "");
+
+        let mito = GeneticCode::from_id(2).unwrap();
+        assert_eq!(dna.translate(0, &mito), "MAIVMGRWKGAR*");
+        let to_stop = TranslateOptions::new().to_stop(true);
+        assert_eq!(to_stop.translate(dna.as_slice(), 0, &standard), "MAIVMGR");
+        assert_eq!(to_stop.translate(dna.as_slice(), 0, &mito), "MAIVMGRWKGAR");
+
+        let alt = PackedDna::from_str("GTGAAAGTG").unwrap();
+        let bacterial = GeneticCode::from_id(11).unwrap();
+        assert_eq!(alt.translate(0, &bacterial), "VKV");
+        let starts = TranslateOptions::new().alternative_starts(true);
+        assert_eq!(starts.translate(alt.as_slice(), 0, &bacterial), "MKV");
+        assert_eq!(starts.translate(alt.as_slice(), 0, &standard), "VKV");
+    }
+
+    // Test to check that an invalid frame is rejected
+    #[test]
+    #[should_panic]
+    fn test_translate_bad_frame() {
+        PackedDna::from_str(CDS)
+            .unwrap()
+            .translate(3, &GeneticCode::standard());
+    }
+}
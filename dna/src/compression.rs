@@ -0,0 +1,193 @@
+//! Transparent decompression of gzip and BGZF input.
+//!
+//! [`Reader`] looks at the first bytes of its source and decompresses it if
+//! they are a gzip or BGZF header, so the FASTA and FASTQ readers accept
+//! `.gz` files without being told. BGZF sources are read through
+//! [`bgzf::Reader`] and keep their block-level random access.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use flate2::bufread::MultiGzDecoder;
+
+use crate::bgzf;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The compression of an input, as detected from its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// Uncompressed.
+    Plain,
+    /// gzip, possibly several concatenated members.
+    Gzip,
+    /// BGZF, the blocked gzip written by `bgzip`.
+    Bgzf,
+}
+
+impl Format {
+    /// Detects the compression of `inner` by peeking at its first bytes,
+    /// without consuming them.
+    pub fn detect<R: BufRead>(inner: &mut R) -> io::Result<Format> {
+        let header = inner.fill_buf()?;
+        Ok(if bgzf::is_bgzf_header(header) {
+            Format::Bgzf
+        } else if header.starts_with(&GZIP_MAGIC) {
+            Format::Gzip
+        } else {
+            Format::Plain
+        })
+    }
+}
+
+#[derive(Debug)]
+enum Inner<R: BufRead> {
+    Plain(R),
+    Gzip(BufReader<MultiGzDecoder<R>>),
+    Bgzf(bgzf::Reader<R>),
+}
+
+/// A reader that decompresses gzip and BGZF input and passes anything else
+/// through unchanged.
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use dna::compression::{Format, Reader};
+///
+/// let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+/// encoder.write_all(b">chr1\nACGT\n").unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// let mut reader = Reader::new(&compressed[..]).unwrap();
+/// assert_eq!(reader.format(), Format::Gzip);
+/// let mut text = String::new();
+/// reader.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, ">chr1\nACGT\n");
+/// ```
+#[derive(Debug)]
+pub struct Reader<R: BufRead> {
+    inner: Inner<R>,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a reader over `inner`, detecting its compression from the
+    /// first bytes.
+    pub fn new(mut inner: R) -> io::Result<Reader<R>> {
+        let inner = match Format::detect(&mut inner)? {
+            Format::Plain => Inner::Plain(inner),
+            Format::Gzip => Inner::Gzip(BufReader::new(MultiGzDecoder::new(inner))),
+            Format::Bgzf => Inner::Bgzf(bgzf::Reader::new(inner)),
+        };
+        Ok(Reader { inner })
+    }
+
+    /// Returns the detected compression.
+    pub fn format(&self) -> Format {
+        match self.inner {
+            Inner::Plain(_) => Format::Plain,
+            Inner::Gzip(_) => Format::Gzip,
+            Inner::Bgzf(_) => Format::Bgzf,
+        }
+    }
+
+    /// Returns the BGZF reader if the input is BGZF, for random access.
+    pub fn as_bgzf_mut(&mut self) -> Option<&mut bgzf::Reader<R>> {
+        match &mut self.inner {
+            Inner::Bgzf(reader) => Some(reader),
+            _ => None,
+        }
+    }
+}
+
+impl Reader<BufReader<File>> {
+    /// Opens the file at `path`, detecting its compression.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Reader<BufReader<File>>> {
+        Reader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(reader) => reader.read(buf),
+            Inner::Gzip(reader) => reader.read(buf),
+            Inner::Bgzf(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for Reader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match &mut self.inner {
+            Inner::Plain(reader) => reader.fill_buf(),
+            Inner::Gzip(reader) => reader.fill_buf(),
+            Inner::Bgzf(reader) => reader.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match &mut self.inner {
+            Inner::Plain(reader) => reader.consume(amt),
+            Inner::Gzip(reader) => reader.consume(amt),
+            Inner::Bgzf(reader) => reader.consume(amt),
+        }
+    }
+}
+
+/// Seeks by uncompressed offset. Seeking fails for gzip input, which can
+/// only be read from the start; use BGZF for random access instead.
+impl<R: BufRead + Seek> Seek for Reader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Plain(reader) => reader.seek(pos),
+            Inner::Gzip(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek in gzip input, compress it with bgzip instead",
+            )),
+            Inner::Bgzf(reader) => reader.seek(pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    // Test to check that plain, gzip and BGZF input are told apart and all
+    // read back the same, including multi-member gzip
+    #[test]
+    fn test_detect_formats() {
+        let text = b">chr1 test\nACGTACGT\nACGT\n>chr2\nTTTT\n";
+
+        let mut gzip = Vec::new();
+        for half in [&text[..20], &text[20..]] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(half).unwrap();
+            gzip.extend(encoder.finish().unwrap());
+        }
+        let mut bgzf = bgzf::Writer::new(Vec::new());
+        bgzf.write_all(text).unwrap();
+        let bgzf = bgzf.finish().unwrap();
+
+        for (input, format) in [
+            (&text[..], Format::Plain),
+            (&gzip[..], Format::Gzip),
+            (&bgzf[..], Format::Bgzf),
+            (&b""[..], Format::Plain),
+        ] {
+            let mut reader = Reader::new(input).unwrap();
+            assert_eq!(reader.format(), format);
+            assert_eq!(reader.as_bgzf_mut().is_some(), format == Format::Bgzf);
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            if !input.is_empty() {
+                assert_eq!(out, text);
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Per-nucleotide counts of packed sequences.
+
+use std::{
+    iter::FromIterator,
+    ops::{Add, AddAssign, Index},
+};
+
+use crate::Nuc;
+
+// selects the low bit of every two bit slot in a word
+const LOW_BITS: u64 = 0x5555_5555_5555_5555;
+
+/// The number of times each nucleotide occurs in a sequence, as returned
+/// by [`PackedDna::counts`](crate::PackedDna::counts) and
+/// [`PackedDnaSlice::counts`](crate::PackedDnaSlice::counts).
+///
+/// Sequences with `N` runs, such as [`MaskedPackedDna`](crate::MaskedPackedDna),
+/// also report how many positions are `N`; those are included in
+/// [`NucCounts::total`] but not in [`NucCounts::gc_fraction`].
+///
+/// Counts from several sequences can be merged with `+` or `+=`.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{Nuc, PackedDna};
+///
+/// let counts = PackedDna::from_str("ACGTTT").unwrap().counts();
+/// assert_eq!(counts[Nuc::T], 3);
+/// assert_eq!(counts.total(), 6);
+/// assert_eq!(counts.gc_fraction(), Some(2.0 / 6.0));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NucCounts {
+    a: usize,
+    c: usize,
+    g: usize,
+    t: usize,
+    n: usize,
+}
+
+impl NucCounts {
+    /// Creates counts with every nucleotide at zero.
+    pub fn new() -> NucCounts {
+        NucCounts::default()
+    }
+
+    /// Counts the first `len` nucleotides of `words`, which must be packed
+    /// 32 per word from the low bits with any bits past `len` zeroed.
+    ///
+    /// Rather than decoding each nucleotide, every word is split into the
+    /// low and high bits of its slots and the matching combinations are
+    /// counted with `count_ones`; the padding reads as A and is accounted
+    /// for by deriving A from the total.
+    pub(crate) fn from_words<I: IntoIterator<Item = u64>>(words: I, len: usize) -> NucCounts {
+        let (mut c, mut g, mut t) = (0, 0, 0);
+        for word in words {
+            let low = word & LOW_BITS;
+            let high = (word >> 1) & LOW_BITS;
+            c += (low & !high).count_ones() as usize;
+            g += (high & !low).count_ones() as usize;
+            t += (low & high).count_ones() as usize;
+        }
+        NucCounts {
+            a: len - c - g - t,
+            c,
+            g,
+            t,
+            n: 0,
+        }
+    }
+
+    /// Reclassifies `n` positions counted as A as `N` instead. Sequences
+    /// store A underneath their `N` runs, so this turns the counts of the
+    /// underlying bases into the counts of the masked sequence.
+    pub(crate) fn mask_n(&mut self, n: usize) {
+        self.a -= n;
+        self.n += n;
+    }
+
+    /// Returns the number of times `nuc` occurs.
+    pub fn count(&self, nuc: Nuc) -> usize {
+        self[nuc]
+    }
+
+    /// Increments the count of `nuc` by one.
+    pub fn add_nuc(&mut self, nuc: Nuc) {
+        match nuc {
+            Nuc::A => self.a += 1,
+            Nuc::C => self.c += 1,
+            Nuc::G => self.g += 1,
+            Nuc::T => self.t += 1,
+        }
+    }
+
+    /// Returns the number of `N` positions counted.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the total number of positions counted, `N` included.
+    pub fn total(&self) -> usize {
+        self.a + self.c + self.g + self.t + self.n
+    }
+
+    /// Returns the fraction of counted nucleotides that are G or C, or
+    /// `None` if no nucleotide was counted. `N` positions are left out.
+    pub fn gc_fraction(&self) -> Option<f64> {
+        match self.a + self.c + self.g + self.t {
+            0 => None,
+            called => Some((self.g + self.c) as f64 / called as f64),
+        }
+    }
+}
+
+impl Index<Nuc> for NucCounts {
+    type Output = usize;
+
+    fn index(&self, nuc: Nuc) -> &usize {
+        match nuc {
+            Nuc::A => &self.a,
+            Nuc::C => &self.c,
+            Nuc::G => &self.g,
+            Nuc::T => &self.t,
+        }
+    }
+}
+
+impl Add for NucCounts {
+    type Output = NucCounts;
+
+    fn add(mut self, other: NucCounts) -> NucCounts {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for NucCounts {
+    fn add_assign(&mut self, other: NucCounts) {
+        self.a += other.a;
+        self.c += other.c;
+        self.g += other.g;
+        self.t += other.t;
+        self.n += other.n;
+    }
+}
+
+impl FromIterator<Nuc> for NucCounts {
+    fn from_iter<I: IntoIterator<Item = Nuc>>(iter: I) -> NucCounts {
+        let mut counts = NucCounts::new();
+        for nuc in iter {
+            counts.add_nuc(nuc);
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackedDna;
+    use std::str::FromStr;
+
+    const TEXT: &str = "GGCATTACGATCAGGGATCCCTAGCATGCATTTACGACTAGCAGCGGCTATATATCAGCG";
+
+    // Test to check that the word-level counts agree with counting one
+    // nucleotide at a time, for every slice of the sequence
+    #[test]
+    fn test_counts_match_per_base() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        for start in 0..TEXT.len() {
+            for end in start..=TEXT.len() {
+                let slice = dna.slice(start..end);
+                let expected: NucCounts = slice.iter().collect();
+                assert_eq!(slice.counts(), expected);
+                assert_eq!(slice.counts().total(), end - start);
+            }
+        }
+        assert_eq!(dna.counts(), dna.iter().collect());
+    }
+
+    // Test to check the accessors and merging
+    #[test]
+    fn test_counts_accessors() {
+        let counts = PackedDna::from_str("ACGTTT").unwrap().counts();
+        assert_eq!(counts[Nuc::A], 1);
+        assert_eq!(counts.count(Nuc::C), 1);
+        assert_eq!(counts[Nuc::G], 1);
+        assert_eq!(counts[Nuc::T], 3);
+        assert_eq!(counts.gc_fraction(), Some(2.0 / 6.0));
+        assert_eq!(NucCounts::new().gc_fraction(), None);
+
+        let mut merged = counts + PackedDna::from_str("GG").unwrap().counts();
+        assert_eq!(merged[Nuc::G], 3);
+        assert_eq!(merged.total(), 8);
+        merged += counts;
+        assert_eq!(merged[Nuc::T], 6);
+        assert_eq!(merged.gc_fraction(), Some(6.0 / 14.0));
+    }
+}
@@ -0,0 +1,592 @@
+//! `.fai` indexes and random access to indexed FASTA files.
+
+use std::{
+    convert::TryFrom,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+};
+
+use super::{split_header, Error};
+use crate::{
+    bgzf::GziIndex, compression, LengthOverflow, MaskedNuc, MaskedPackedDna, Nuc, PackedDna,
+    PackedDnaBuilder,
+};
+
+/// The entry of a `.fai` index for one sequence, locating its nucleotides in
+/// the FASTA file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexRecord {
+    /// The sequence identifier.
+    pub name: String,
+    /// The number of nucleotides.
+    pub length: u64,
+    /// The byte offset of the first nucleotide.
+    pub offset: u64,
+    /// The number of nucleotides on every full line.
+    pub line_bases: u64,
+    /// The number of bytes on every full line, including its line ending.
+    pub line_width: u64,
+}
+
+impl IndexRecord {
+    /// Returns the byte offset of the nucleotide at `pos`.
+    fn offset_of(&self, pos: u64) -> u64 {
+        if self.line_bases == 0 {
+            return self.offset;
+        }
+        self.offset + pos / self.line_bases * self.line_width + pos % self.line_bases
+    }
+
+    /// Returns the byte offsets spanned by the nucleotides in `range`.
+    fn byte_range(&self, range: &Range<u64>) -> Range<u64> {
+        let start = self.offset_of(range.start);
+        if range.is_empty() {
+            start..start
+        } else {
+            start..self.offset_of(range.end - 1) + 1
+        }
+    }
+}
+
+/// A samtools compatible FASTA index, as written by `samtools faidx`.
+///
+/// Every sequence must be wrapped at the same number of nucleotides on all
+/// of its lines but the last, which is what lets a position be turned into
+/// a byte offset.
+///
+/// ```
+/// use dna::fasta::{Index, IndexedReader};
+/// use std::io::Cursor;
+///
+/// let text = ">chr1\nACGTA\nCCGGT\nT\n>chr2\nGGGG\n";
+/// let index = Index::build(text.as_bytes()).unwrap();
+///
+/// let mut fai = Vec::new();
+/// index.write_to(&mut fai).unwrap();
+/// assert_eq!(fai, b"chr1\t11\t6\t5\t6\nchr2\t4\t26\t4\t5\n");
+///
+/// let mut reader = IndexedReader::new(Cursor::new(text), index).unwrap();
+/// let seq = reader.fetch_region("chr1:4-8").unwrap();
+/// assert_eq!(seq.to_string(), "TACCG");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Index {
+    records: Vec<IndexRecord>,
+}
+
+impl Index {
+    /// Indexes the FASTA text read from `reader`.
+    ///
+    /// Fails with [`Error::InconsistentLineLength`] if a sequence is not
+    /// wrapped evenly.
+    pub fn build<R: BufRead>(mut reader: R) -> Result<Index, Error> {
+        let mut records = Vec::new();
+        // the record being indexed, and whether its last line was seen
+        let mut current: Option<(IndexRecord, bool)> = None;
+        let mut buf = Vec::new();
+        let (mut offset, mut line) = (0u64, 0usize);
+        loop {
+            buf.clear();
+            let width = reader.read_until(b'\n', &mut buf)?;
+            if width == 0 {
+                break;
+            }
+            line += 1;
+            let mut content = &buf[..];
+            for ending in [b'\n', b'\r'] {
+                if content.last() == Some(&ending) {
+                    content = &content[..content.len() - 1];
+                }
+            }
+            let (width, bases) = (width as u64, content.len() as u64);
+
+            if let Some(header) = content.strip_prefix(b">") {
+                records.extend(current.take().map(|(record, _)| record));
+                let (name, _) = split_header(&String::from_utf8_lossy(header));
+                if name.is_empty() {
+                    return Err(Error::EmptyId { line });
+                }
+                let record = IndexRecord {
+                    name,
+                    length: 0,
+                    offset: offset + width,
+                    line_bases: 0,
+                    line_width: 0,
+                };
+                current = Some((record, false));
+            } else if let Some((record, ended)) = &mut current {
+                if content.is_empty() {
+                    // blank lines end the sequence, or are skipped before it
+                    if record.length == 0 {
+                        record.offset += width;
+                    } else {
+                        *ended = true;
+                    }
+                } else if *ended || (record.line_bases > 0 && bases > record.line_bases) {
+                    return Err(Error::InconsistentLineLength { line });
+                } else {
+                    if record.line_bases == 0 {
+                        record.line_bases = bases;
+                        record.line_width = width;
+                    } else if bases < record.line_bases
+                        || width - bases != record.line_width - record.line_bases
+                    {
+                        *ended = true;
+                    }
+                    record.length += bases;
+                }
+            } else if !content.is_empty() {
+                return Err(Error::MissingHeader { line });
+            }
+            offset += width;
+        }
+        records.extend(current.map(|(record, _)| record));
+        Ok(Index { records })
+    }
+
+    /// Reads an index in the `.fai` format: one tab separated line per
+    /// sequence with its name, length, offset, line bases and line width.
+    /// Any further columns, such as the quality offsets of FASTQ indexes,
+    /// are ignored.
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Index, Error> {
+        let mut records = Vec::new();
+        for (idx, text) in reader.lines().enumerate() {
+            let text = text?;
+            if text.is_empty() {
+                continue;
+            }
+            let invalid = || Error::InvalidIndex { line: idx + 1 };
+            let fields: Vec<&str> = text.split('\t').collect();
+            if fields.len() < 5 || fields[0].is_empty() {
+                return Err(invalid());
+            }
+            let mut numbers = [0u64; 4];
+            for (number, field) in numbers.iter_mut().zip(&fields[1..5]) {
+                *number = field.parse().map_err(|_| invalid())?;
+            }
+            let [length, offset, line_bases, line_width] = numbers;
+            if line_width < line_bases || (line_bases == 0 && length > 0) {
+                return Err(invalid());
+            }
+            records.push(IndexRecord {
+                name: fields[0].to_string(),
+                length,
+                offset,
+                line_bases,
+                line_width,
+            });
+        }
+        Ok(Index { records })
+    }
+
+    /// Writes the index in the `.fai` format.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for record in &self.records {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                record.name, record.length, record.offset, record.line_bases, record.line_width
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the entries in file order.
+    pub fn records(&self) -> &[IndexRecord] {
+        &self.records
+    }
+
+    /// Returns the entry for the sequence called `name`.
+    pub fn get(&self, name: &str) -> Option<&IndexRecord> {
+        self.records.iter().find(|record| record.name == name)
+    }
+}
+
+/// Parses a 1-based position of a region, which may contain thousands
+/// separators.
+fn parse_position(text: &str) -> Option<u64> {
+    let digits: String = text.chars().filter(|&c| c != ',').collect();
+    digits.parse().ok().filter(|&pos| pos > 0)
+}
+
+/// A reader that fetches subsequences of an indexed FASTA file, seeking
+/// straight to them instead of reading the file from the start.
+///
+/// Any seekable source works, including a [`bgzf::Reader`](crate::bgzf::Reader)
+/// over a bgzip compressed file, in which case the offsets in the index are
+/// uncompressed offsets.
+#[derive(Debug)]
+pub struct IndexedReader<R> {
+    inner: R,
+    index: Index,
+    buf: Vec<u8>,
+}
+
+impl<R: Read + Seek> IndexedReader<R> {
+    /// Creates a reader over `inner` using `index`.
+    ///
+    /// Fails with [`Error::IndexMismatch`] if a sequence in the index would
+    /// extend past the end of `inner`, which usually means the index was
+    /// built for another file.
+    pub fn new(mut inner: R, index: Index) -> Result<IndexedReader<R>, Error> {
+        let size = inner.seek(SeekFrom::End(0))?;
+        for record in &index.records {
+            if record.byte_range(&(0..record.length)).end > size {
+                return Err(Error::IndexMismatch {
+                    name: record.name.clone(),
+                });
+            }
+        }
+        Ok(IndexedReader {
+            inner,
+            index,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Returns the index.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Fetches the nucleotides in `range`, counted from 0, of the sequence
+    /// called `name`.
+    ///
+    /// Fails with [`Error::InvalidSequence`] if the range holds anything
+    /// but A, C, G and T; use [`IndexedReader::fetch_masked`] for ranges
+    /// that may hold `N`.
+    pub fn fetch(&mut self, name: &str, range: Range<u64>) -> Result<PackedDna, Error> {
+        let mut builder = PackedDnaBuilder::with_capacity((range.end - range.start) as usize);
+        self.read_range(name, range, |byte| match Nuc::try_from(char::from(byte)) {
+            Ok(nuc) => builder.push(nuc).map(|_| true),
+            Err(_) => Ok(false),
+        })?;
+        Ok(builder.build())
+    }
+
+    /// Fetches the symbols in `range`, counted from 0, of the sequence
+    /// called `name`, keeping `N` as runs and lowercase positions as
+    /// soft-masked, the way [`twobit::Reader::read_masked`] does.
+    ///
+    /// [`twobit::Reader::read_masked`]: crate::twobit::Reader::read_masked
+    pub fn fetch_masked(
+        &mut self,
+        name: &str,
+        range: Range<u64>,
+    ) -> Result<MaskedPackedDna, Error> {
+        let mut seq = MaskedPackedDna::new();
+        self.read_range(name, range, |byte| {
+            match MaskedNuc::try_from(char::from(byte)) {
+                Ok(symbol) => {
+                    seq.push_with_mask(symbol, byte.is_ascii_lowercase());
+                    Ok(true)
+                }
+                Err(_) => Ok(false),
+            }
+        })?;
+        Ok(seq)
+    }
+
+    /// Fetches a region written the samtools way: `name` for a whole
+    /// sequence, `name:start` from `start` to its end, or
+    /// `name:start-end`, with both positions counted from 1 and included.
+    /// An end past the end of the sequence is clamped to it.
+    ///
+    /// Like [`IndexedReader::fetch`], this only accepts A, C, G and T; use
+    /// [`IndexedReader::fetch_region_masked`] for regions that may hold
+    /// `N`.
+    pub fn fetch_region(&mut self, region: &str) -> Result<PackedDna, Error> {
+        let (name, range) = self.parse_region(region)?;
+        self.fetch(name, range)
+    }
+
+    /// Fetches a region written as for [`IndexedReader::fetch_region`],
+    /// keeping its `N` runs and soft mask like
+    /// [`IndexedReader::fetch_masked`].
+    pub fn fetch_region_masked(&mut self, region: &str) -> Result<MaskedPackedDna, Error> {
+        let (name, range) = self.parse_region(region)?;
+        self.fetch_masked(name, range)
+    }
+
+    /// Resolves a samtools style region into a sequence name and a range
+    /// counted from 0.
+    fn parse_region<'r>(&self, region: &'r str) -> Result<(&'r str, Range<u64>), Error> {
+        // names may contain colons themselves, so prefer a whole match
+        if let Some(record) = self.index.get(region) {
+            return Ok((region, 0..record.length));
+        }
+        let invalid = || Error::InvalidRegion {
+            region: region.to_string(),
+        };
+        let (name, span) = region
+            .rsplit_once(':')
+            .ok_or_else(|| Error::UnknownSequence {
+                name: region.to_string(),
+            })?;
+        let length = self
+            .index
+            .get(name)
+            .ok_or_else(|| Error::UnknownSequence {
+                name: name.to_string(),
+            })?
+            .length;
+        let (start, end) = match span.split_once('-') {
+            Some((start, end)) => (
+                parse_position(start).ok_or_else(invalid)?,
+                parse_position(end).ok_or_else(invalid)?,
+            ),
+            None => (parse_position(span).ok_or_else(invalid)?, length),
+        };
+        let end = end.min(length);
+        if start > end + 1 {
+            return Err(invalid());
+        }
+        Ok((name, start - 1..end))
+    }
+
+    /// Reads the bytes of `range` of the sequence called `name` and feeds
+    /// every one but line breaks to `push`, which returns whether the byte
+    /// is a valid symbol.
+    fn read_range<F>(&mut self, name: &str, range: Range<u64>, mut push: F) -> Result<(), Error>
+    where
+        F: FnMut(u8) -> Result<bool, LengthOverflow>,
+    {
+        let record = self.index.get(name).ok_or_else(|| Error::UnknownSequence {
+            name: name.to_string(),
+        })?;
+        let invalid = || Error::InvalidRegion {
+            region: format!("{}:{}-{}", name, range.start + 1, range.end),
+        };
+        if range.start > range.end || range.end > record.length {
+            return Err(invalid());
+        }
+        let bytes = record.byte_range(&range);
+        self.inner.seek(SeekFrom::Start(bytes.start))?;
+        self.buf.resize((bytes.end - bytes.start) as usize, 0);
+        self.inner.read_exact(&mut self.buf)?;
+
+        let mut count = 0u64;
+        for &byte in self
+            .buf
+            .iter()
+            .filter(|&&byte| byte != b'\n' && byte != b'\r')
+        {
+            if !push(byte).map_err(|_| invalid())? {
+                return Err(Error::InvalidSequence {
+                    name: name.to_string(),
+                    position: range.start + count + 1,
+                    symbol: char::from(byte),
+                });
+            }
+            count += 1;
+        }
+        if count != range.end - range.start {
+            // line breaks where the index does not expect them
+            return Err(Error::IndexMismatch {
+                name: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl IndexedReader<compression::Reader<BufReader<File>>> {
+    /// Opens the FASTA file at `path` with the index next to it at
+    /// `<path>.fai`.
+    ///
+    /// The file may be bgzip compressed, in which case the block index at
+    /// `<path>.gzi` is used if it exists and built otherwise. Files
+    /// compressed with plain gzip cannot be read at random and are refused.
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<IndexedReader<compression::Reader<BufReader<File>>>, Error> {
+        let path = path.as_ref();
+        let sibling = |extension: &str| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(extension);
+            name
+        };
+        let index = Index::read_from(BufReader::new(File::open(sibling(".fai"))?))?;
+        let mut inner = compression::Reader::from_path(path)?;
+        if let Some(bgzf) = inner.as_bgzf_mut() {
+            match File::open(sibling(".gzi")) {
+                Ok(gzi) => bgzf.set_index(GziIndex::read_from(BufReader::new(gzi))?),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        IndexedReader::new(inner, index)
+    }
+}
+
+/// Builds the `.fai` index of the FASTA file at `path`, which may be
+/// compressed, and writes it to `<path>.fai`.
+pub fn index_path<P: AsRef<Path>>(path: P) -> Result<Index, Error> {
+    let path = path.as_ref();
+    let index = Index::build(compression::Reader::from_path(path)?)?;
+    let mut fai = path.as_os_str().to_owned();
+    fai.push(".fai");
+    let mut text = Vec::new();
+    index.write_to(&mut text)?;
+    fs::write(fai, text)?;
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const TEXT: &str = ">chr1 first\r\nACGTA\r\nCCGGT\r\nTT\r\n>chr2\n\n>chr:3\nGGGGAAAA\nTTTTCC\n";
+
+    // Test to check the offsets of a built index, reading it back from its
+    // text form, and that uneven wrapping is refused
+    #[test]
+    fn test_build_index() {
+        let index = Index::build(TEXT.as_bytes()).unwrap();
+        let mut fai = Vec::new();
+        index.write_to(&mut fai).unwrap();
+        assert_eq!(
+            String::from_utf8(fai.clone()).unwrap(),
+            "chr1\t12\t13\t5\t7\nchr2\t0\t38\t0\t0\nchr:3\t14\t45\t8\t9\n"
+        );
+        assert_eq!(Index::read_from(&fai[..]).unwrap(), index);
+        assert_eq!(index.get("chr:3").unwrap().offset, 45);
+
+        assert!(matches!(
+            Index::build(">a\nACG\nACGT\nA\n".as_bytes()),
+            Err(Error::InconsistentLineLength { line: 3 })
+        ));
+        assert!(matches!(
+            Index::build(">a\nACGT\nAC\nAC\n".as_bytes()),
+            Err(Error::InconsistentLineLength { line: 4 })
+        ));
+        assert!(matches!(
+            Index::read_from("chr1\t12\tx\t5\t7\n".as_bytes()),
+            Err(Error::InvalidIndex { line: 1 })
+        ));
+    }
+
+    // Test to check fetching regions across line breaks from plain and
+    // BGZF sources, and the errors for bad regions and stale indexes
+    #[test]
+    fn test_fetch() {
+        let index = Index::build(TEXT.as_bytes()).unwrap();
+        let mut bgzf = crate::bgzf::Writer::new(Vec::new());
+        // a tiny first block puts the sequences in later blocks
+        bgzf.write_all(&TEXT.as_bytes()[..10]).unwrap();
+        bgzf.flush().unwrap();
+        bgzf.write_all(&TEXT.as_bytes()[10..]).unwrap();
+        let compressed = bgzf.finish().unwrap();
+        let plain = IndexedReader::new(Cursor::new(TEXT.as_bytes()), index.clone()).unwrap();
+        let bgzf = crate::bgzf::Reader::new(Cursor::new(compressed));
+        let bgzf = IndexedReader::new(bgzf, index.clone()).unwrap();
+
+        fn check<R: Read + Seek>(mut reader: IndexedReader<R>) {
+            let fetch = |reader: &mut IndexedReader<R>, region: &str| {
+                reader.fetch_region(region).unwrap().to_string()
+            };
+            assert_eq!(fetch(&mut reader, "chr1"), "ACGTACCGGTTT");
+            assert_eq!(fetch(&mut reader, "chr1:5-6"), "AC");
+            assert_eq!(fetch(&mut reader, "chr1:10"), "TTT");
+            assert_eq!(fetch(&mut reader, "chr1:1,1-1,000"), "TT");
+            assert_eq!(fetch(&mut reader, "chr2"), "");
+            assert_eq!(fetch(&mut reader, "chr:3"), "GGGGAAAATTTTCC");
+            assert_eq!(fetch(&mut reader, "chr:3:8-9"), "AT");
+            assert_eq!(reader.fetch("chr:3", 8..8).unwrap().len(), 0);
+            assert!(matches!(
+                reader.fetch_region("chr9:1-2"),
+                Err(Error::UnknownSequence { .. })
+            ));
+            assert!(matches!(
+                reader.fetch_region("chr1:0-2"),
+                Err(Error::InvalidRegion { .. })
+            ));
+            assert!(matches!(
+                reader.fetch("chr1", 3..13),
+                Err(Error::InvalidRegion { .. })
+            ));
+        }
+        check(plain);
+        check(bgzf);
+
+        let truncated = &TEXT.as_bytes()[..TEXT.len() - 4];
+        assert!(matches!(
+            IndexedReader::new(Cursor::new(truncated), index.clone()),
+            Err(Error::IndexMismatch { name }) if name == "chr:3"
+        ));
+        let shifted = TEXT.replacen("first", "1st", 1) + "\n\n";
+        let mut reader =
+            IndexedReader::new(Cursor::new(shifted.as_bytes()), index.clone()).unwrap();
+        assert!(matches!(
+            reader.fetch_region("chr1"),
+            Err(Error::IndexMismatch { .. })
+        ));
+        // the strict fetch refuses N, the masked one keeps it with the mask
+        let ambiguous = TEXT.replacen("CCGGT", "CCNgt", 1);
+        let mut reader = IndexedReader::new(Cursor::new(ambiguous.as_bytes()), index).unwrap();
+        assert!(matches!(
+            reader.fetch_region("chr1:2-12"),
+            Err(Error::InvalidSequence {
+                position: 8,
+                symbol: 'N',
+                ..
+            })
+        ));
+        let masked = reader.fetch_region_masked("chr1:2-12").unwrap();
+        assert_eq!(masked.to_string(), "CGTACCNgtTT");
+        assert!(masked.is_n(6) && !masked.is_n(5) && !masked.is_n(7));
+        assert!((0..11).all(|idx| masked.is_masked(idx) == (7..9).contains(&idx)));
+        assert_eq!(reader.fetch_masked("chr1", 0..12).unwrap().counts().n(), 1);
+        assert_eq!(
+            reader.fetch_region_masked("chr:3:8-9").unwrap().to_string(),
+            "AT"
+        );
+        assert!(matches!(
+            reader.fetch_masked("chr1", 3..13),
+            Err(Error::InvalidRegion { .. })
+        ));
+    }
+
+    // Test to check indexing and opening a bgzip compressed file on disk,
+    // with and without its block index
+    #[test]
+    fn test_from_path() {
+        let mut bgzf = crate::bgzf::Writer::new(Vec::new());
+        bgzf.write_all(TEXT.as_bytes()).unwrap();
+        let compressed = bgzf.finish().unwrap();
+        let path = std::env::temp_dir().join(format!("dna-index-{}.fa.gz", std::process::id()));
+        fs::write(&path, &compressed).unwrap();
+        let mut gzi_path = path.as_os_str().to_owned();
+        gzi_path.push(".gzi");
+        let mut fai_path = path.as_os_str().to_owned();
+        fai_path.push(".fai");
+
+        let index = index_path(&path).unwrap();
+        assert_eq!(index, Index::build(TEXT.as_bytes()).unwrap());
+        let mut reader = IndexedReader::from_path(&path).unwrap();
+        assert_eq!(
+            reader.fetch_region("chr:3:5-10").unwrap().to_string(),
+            "AAAATT"
+        );
+        let mut gzi = Vec::new();
+        GziIndex::build(Cursor::new(&compressed))
+            .unwrap()
+            .write_to(&mut gzi)
+            .unwrap();
+        fs::write(&gzi_path, gzi).unwrap();
+        let mut reader = IndexedReader::from_path(&path).unwrap();
+        assert_eq!(reader.fetch_region("chr1:6-7").unwrap().to_string(), "CC");
+
+        for file in [path.into_os_string(), gzi_path, fai_path] {
+            fs::remove_file(file).unwrap();
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Reading and writing packed sequences as FASTA.
+//!
+//! ```
+//! use dna::fasta::Reader;
+//!
+//! let text = ">chr1 first chromosome\nACGT\nGG\n>chr2\nTTAA\n";
+//! let records = Reader::new(text.as_bytes())
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(records[0].id, "chr1");
+//! assert_eq!(records[0].description.as_deref(), Some("first chromosome"));
+//! assert_eq!(records[0].seq.to_string(), "ACGTGG");
+//! assert_eq!(records[1].seq.to_string(), "TTAA");
+//! ```
+
+use std::io;
+
+mod index;
+mod reader;
+mod writer;
+
+pub use index::{index_path, Index, IndexRecord, IndexedReader};
+pub use reader::{MaskedRecord, Reader, Record};
+pub use writer::{Writer, DEFAULT_LINE_WIDTH};
+
+/// Splits a header line, without its leading `>` or `@`, into the
+/// identifier and the description.
+pub(crate) fn split_header(header: &str) -> (String, Option<String>) {
+    let header = header.trim();
+    match header.find(char::is_whitespace) {
+        Some(end) => {
+            let description = header[end..].trim_start();
+            (header[..end].to_string(), Some(description.to_string()))
+        }
+        None => (header.to_string(), None),
+    }
+}
+
+/// An error that can occur while reading FASTA.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Reading from the underlying source failed.
+    #[error("failed to read FASTA: {0}")]
+    Io(#[from] io::Error),
+    /// Sequence data appeared before the first `>` header.
+    #[error("line {line}: sequence data before the first '>' header")]
+    MissingHeader {
+        /// The offending line, counting from 1.
+        line: usize,
+    },
+    /// A header had no identifier after the `>`.
+    #[error("line {line}: header has no identifier")]
+    EmptyId {
+        /// The offending line, counting from 1.
+        line: usize,
+    },
+    /// A sequence line contained a character that is not a nucleotide.
+    #[error("line {line}, column {column}: invalid nucleotide {symbol:?}")]
+    InvalidSymbol {
+        /// The offending line, counting from 1.
+        line: usize,
+        /// The character position within the line, counting from 1.
+        column: usize,
+        /// The offending character.
+        symbol: char,
+    },
+    /// A record holds more nucleotides than a
+    /// [`PackedDna`](crate::PackedDna) can store.
+    #[error("line {line}: sequence is too long")]
+    LengthOverflow {
+        /// The line on which the sequence overflowed, counting from 1.
+        line: usize,
+    },
+    /// Sequence lines of a record were wrapped at different lengths, so it
+    /// cannot be indexed.
+    #[error("line {line}: sequence lines must all have the same length")]
+    InconsistentLineLength {
+        /// The offending line, counting from 1.
+        line: usize,
+    },
+    /// A line of a `.fai` index could not be parsed.
+    #[error("line {line}: malformed index entry")]
+    InvalidIndex {
+        /// The offending line of the index, counting from 1.
+        line: usize,
+    },
+    /// The index does not describe the file it is used with.
+    #[error("index entry for {name:?} does not match the file")]
+    IndexMismatch {
+        /// The sequence whose entry does not match.
+        name: String,
+    },
+    /// A sequence is not in the index.
+    #[error("no sequence named {name:?} in the index")]
+    UnknownSequence {
+        /// The requested name.
+        name: String,
+    },
+    /// A region was malformed or out of bounds.
+    #[error("invalid region {region:?}")]
+    InvalidRegion {
+        /// The requested region.
+        region: String,
+    },
+    /// A fetched region contained a character that is not a nucleotide.
+    #[error("{name}:{position}: invalid nucleotide {symbol:?}")]
+    InvalidSequence {
+        /// The sequence the character was found in.
+        name: String,
+        /// The position of the character, counting from 1.
+        position: u64,
+        /// The offending character.
+        symbol: char,
+    },
+}
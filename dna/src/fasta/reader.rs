@@ -0,0 +1,400 @@
+//! A streaming FASTA reader.
+
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+use super::{split_header, Error};
+use crate::{
+    compression, LengthOverflow, MaskedNuc, MaskedPackedDna, Nuc, PackedDna, PackedDnaBuilder,
+};
+
+/// A single FASTA record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Record {
+    /// The identifier: the header up to the first whitespace.
+    pub id: String,
+    /// The rest of the header after the identifier, if any.
+    pub description: Option<String>,
+    /// The sequence.
+    pub seq: PackedDna,
+}
+
+/// A single FASTA record whose sequence may hold `N` runs and soft-masked
+/// regions, read by [`Reader::next_masked`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaskedRecord {
+    /// The identifier: the header up to the first whitespace.
+    pub id: String,
+    /// The rest of the header after the identifier, if any.
+    pub description: Option<String>,
+    /// The sequence.
+    pub seq: MaskedPackedDna,
+}
+
+/// A streaming reader that yields one [`Record`] at a time from FASTA text.
+///
+/// Sequences may be wrapped over any number of lines. Line endings may be
+/// `\n` or `\r\n`, blank lines are skipped, and nucleotides are read case
+/// insensitively. Malformed input is reported as an [`Error`] naming the
+/// line it was found on, after which the iterator ends.
+///
+/// The iterator yields [`Record`]s, which only hold A, C, G and T. Reference
+/// sequences usually contain runs of `N` and lowercase soft-masked repeats;
+/// read those with [`Reader::next_masked`], which mirrors
+/// [`Writer::write_masked`](super::Writer::write_masked).
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+    buf: String,
+    // number of lines read so far
+    line: usize,
+    // header of the next record and the line it was found on, read while
+    // finishing the previous record
+    header: Option<(String, usize)>,
+    done: bool,
+    soft_mask: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a reader over `inner`.
+    pub fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            buf: String::new(),
+            line: 0,
+            header: None,
+            done: false,
+            soft_mask: true,
+        }
+    }
+
+    /// Sets whether lowercase positions are recorded as soft-masked in the
+    /// records read by [`Reader::next_masked`]. Enabled by default, like
+    /// [`Writer::soft_mask`](super::Writer::soft_mask); when disabled, case
+    /// is ignored.
+    pub fn soft_mask(mut self, yes: bool) -> Reader<R> {
+        self.soft_mask = yes;
+        self
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the next line into `buf` without its line ending, returning
+    /// `false` at the end of the input.
+    fn read_line(&mut self) -> Result<bool, Error> {
+        self.buf.clear();
+        if self.inner.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        let len = self.buf.trim_end_matches(&['\r', '\n'][..]).len();
+        self.buf.truncate(len);
+        Ok(true)
+    }
+
+    /// Reads the next header, returning its identifier and description, or
+    /// `None` at the end of the input.
+    fn read_header(&mut self) -> Result<Option<(String, Option<String>)>, Error> {
+        let (header, header_line) = match self.header.take() {
+            Some(header) => header,
+            None => loop {
+                if !self.read_line()? {
+                    return Ok(None);
+                }
+                if let Some(header) = self.buf.strip_prefix('>') {
+                    break (header.to_string(), self.line);
+                }
+                if !self.buf.trim().is_empty() {
+                    return Err(Error::MissingHeader { line: self.line });
+                }
+            },
+        };
+        let (id, description) = split_header(&header);
+        if id.is_empty() {
+            return Err(Error::EmptyId { line: header_line });
+        }
+        Ok(Some((id, description)))
+    }
+
+    /// Feeds every character of the sequence lines up to the next header to
+    /// `push`, which returns whether the character is a valid symbol.
+    fn read_seq<F>(&mut self, mut push: F) -> Result<(), Error>
+    where
+        F: FnMut(char) -> Result<bool, LengthOverflow>,
+    {
+        while self.read_line()? {
+            if let Some(header) = self.buf.strip_prefix('>') {
+                self.header = Some((header.to_string(), self.line));
+                break;
+            }
+            for (idx, c) in self.buf.trim_end().chars().enumerate() {
+                let valid = push(c).map_err(|_| Error::LengthOverflow { line: self.line })?;
+                if !valid {
+                    return Err(Error::InvalidSymbol {
+                        line: self.line,
+                        column: idx + 1,
+                        symbol: c,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the next record, or `None` at the end of the input.
+    fn read_record(&mut self) -> Result<Option<Record>, Error> {
+        let (id, description) = match self.read_header()? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let mut builder = PackedDnaBuilder::new();
+        self.read_seq(|c| match Nuc::try_from(c) {
+            Ok(nuc) => builder.push(nuc).map(|_| true),
+            Err(_) => Ok(false),
+        })?;
+        Ok(Some(Record {
+            id,
+            description,
+            seq: builder.build(),
+        }))
+    }
+
+    /// Reads the next record keeping its `N` runs, and its soft mask if
+    /// enabled, or `None` at the end of the input.
+    fn read_masked_record(&mut self) -> Result<Option<MaskedRecord>, Error> {
+        let (id, description) = match self.read_header()? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let soft_mask = self.soft_mask;
+        let mut seq = MaskedPackedDna::new();
+        self.read_seq(|c| match MaskedNuc::try_from(c) {
+            Ok(symbol) => {
+                if seq.len() == usize::MAX {
+                    return Err(LengthOverflow);
+                }
+                seq.push_with_mask(symbol, soft_mask && c.is_ascii_lowercase());
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        })?;
+        Ok(Some(MaskedRecord {
+            id,
+            description,
+            seq,
+        }))
+    }
+
+    /// Reads the next record as a [`MaskedRecord`], accepting `N` in the
+    /// sequence, or returns `None` at the end of the input. Records read
+    /// this way and through the iterator may be mixed freely, and errors
+    /// end both.
+    ///
+    /// ```
+    /// use dna::fasta::Reader;
+    ///
+    /// let mut reader = Reader::new(">chr1\nNNNNacgtAC\n".as_bytes());
+    /// let record = reader.next_masked().unwrap().unwrap();
+    /// assert_eq!(record.seq.n_runs(), &[0..4]);
+    /// assert_eq!(record.seq.masked_intervals(), &[4..8]);
+    /// assert!(reader.next_masked().is_none());
+    /// ```
+    pub fn next_masked(&mut self) -> Option<Result<MaskedRecord, Error>> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_masked_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+impl<R: Read> Reader<BufReader<R>> {
+    /// Creates a reader over an unbuffered source, such as a
+    /// [`File`](std::fs::File), wrapping it in a
+    /// [`BufReader`](std::io::BufReader).
+    pub fn from_reader(inner: R) -> Reader<BufReader<R>> {
+        Reader::new(BufReader::new(inner))
+    }
+}
+
+impl Reader<compression::Reader<BufReader<File>>> {
+    /// Opens the FASTA file at `path`, decompressing it if it is gzip or
+    /// BGZF.
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+    ) -> io::Result<Reader<compression::Reader<BufReader<File>>>> {
+        Ok(Reader::new(compression::Reader::from_path(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fasta::Writer, ParseOptions};
+    use std::str::FromStr;
+
+    fn read_all(text: &str) -> Result<Vec<Record>, Error> {
+        Reader::new(text.as_bytes()).collect()
+    }
+
+    // Test to check multi-line records, CRLF endings, blank lines and
+    // headers with and without descriptions
+    #[test]
+    fn test_read_records() {
+        let text = "\r\n>seq1  some description here \r\nACGT\r\nacgt\r\n\r\nTT\r\n>seq2\n\n>seq3\tx\nGGGG";
+        let records = read_all(text).unwrap();
+        assert_eq!(
+            records,
+            [
+                Record {
+                    id: "seq1".to_string(),
+                    description: Some("some description here".to_string()),
+                    seq: PackedDna::from_str("ACGTACGTTT").unwrap(),
+                },
+                Record {
+                    id: "seq2".to_string(),
+                    description: None,
+                    seq: PackedDna::new(),
+                },
+                Record {
+                    id: "seq3".to_string(),
+                    description: Some("x".to_string()),
+                    seq: PackedDna::from_str("GGGG").unwrap(),
+                },
+            ]
+        );
+        assert!(read_all("").unwrap().is_empty());
+        assert!(read_all("\n\n").unwrap().is_empty());
+        let long = format!(">long\n{}\n", "ACGTTGCA".repeat(40));
+        assert_eq!(read_all(&long).unwrap()[0].seq.len(), 320);
+    }
+
+    // Test to check that malformed input is reported with its line number
+    // and ends the iteration
+    #[test]
+    fn test_read_errors() {
+        assert!(matches!(
+            read_all("\nACGT\n>seq\nACGT\n"),
+            Err(Error::MissingHeader { line: 2 })
+        ));
+        assert!(matches!(
+            read_all(">seq\nACGT\n> \nACGT\n"),
+            Err(Error::EmptyId { line: 3 })
+        ));
+        let mut reader = Reader::new(">a\nAC\n>b\nACGT\nACXT\n>c\nA\n".as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap().id, "a");
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::InvalidSymbol {
+                line: 5,
+                column: 3,
+                symbol: 'X'
+            }))
+        ));
+        assert!(reader.next().is_none());
+        let err = read_all(">seq\nAN\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 2: invalid nucleotide 'N'");
+    }
+
+    // Test to check that masked records keep N runs and the soft mask, read
+    // back what the writer wrote, and still reject other symbols
+    #[test]
+    fn test_read_masked() {
+        let seq = ParseOptions::new()
+            .parse_masked_with(&format!("NNNN{}acgtnnACGTNa", "GATTACA".repeat(12)), true)
+            .unwrap();
+        let mut writer = Writer::new(Vec::new()).line_width(30);
+        writer.write_masked("chr1", Some("first"), &seq).unwrap();
+        writer
+            .write_record(&Record {
+                id: "chr2".to_string(),
+                description: None,
+                seq: PackedDna::from_str("ACGT").unwrap(),
+            })
+            .unwrap();
+        let text = writer.into_inner();
+
+        let mut reader = Reader::new(&text[..]);
+        let record = reader.next_masked().unwrap().unwrap();
+        assert_eq!(record.id, "chr1");
+        assert_eq!(record.description.as_deref(), Some("first"));
+        assert_eq!(record.seq, seq);
+        // the plain iterator picks up where the masked read stopped
+        assert_eq!(reader.next().unwrap().unwrap().seq.to_string(), "ACGT");
+        assert!(reader.next_masked().is_none());
+
+        let record = Reader::new(&text[..])
+            .soft_mask(false)
+            .next_masked()
+            .unwrap()
+            .unwrap();
+        assert!(record.seq.masked_intervals().is_empty());
+        assert_eq!(record.seq.n_runs(), seq.n_runs());
+        let mut reader = Reader::new(">a\nACNX\n".as_bytes());
+        assert!(matches!(
+            reader.next_masked(),
+            Some(Err(Error::InvalidSymbol {
+                line: 2,
+                column: 4,
+                symbol: 'X'
+            }))
+        ));
+        assert!(reader.next_masked().is_none());
+    }
+
+    // Test to check that files are read the same whether plain, gzip or
+    // BGZF compressed
+    #[test]
+    fn test_from_path() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let text = b">seq1\nACGT\nGG\n>seq2\nTTA\n";
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(text).unwrap();
+        let mut bgzf = crate::bgzf::Writer::new(Vec::new());
+        bgzf.write_all(text).unwrap();
+        let dir = std::env::temp_dir();
+        for (name, data) in [
+            ("plain.fa", text.to_vec()),
+            ("gzip.fa.gz", gzip.finish().unwrap()),
+            ("bgzf.fa.gz", bgzf.finish().unwrap()),
+        ] {
+            let path = dir.join(format!("dna-fasta-{}-{}", std::process::id(), name));
+            std::fs::write(&path, data).unwrap();
+            let records: Vec<Record> = Reader::from_path(&path)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(records.len(), 2, "{}", name);
+            assert_eq!(records[0].seq, PackedDna::from_str("ACGTGG").unwrap());
+            assert_eq!(records[1].seq, PackedDna::from_str("TTA").unwrap());
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! A streaming FASTA writer.
+
+use std::io::{self, Write};
+
+use super::Record;
+use crate::{MaskedPackedDna, PackedDnaSlice, PackedIupacDna};
+
+/// The line width [`Writer::new`] wraps sequences at.
+pub const DEFAULT_LINE_WIDTH: usize = 60;
+
+/// Writes sequences as FASTA records.
+///
+/// Sequences are decoded straight from their packed storage into the
+/// output, a word at a time, and wrapped at a configurable line width.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{fasta::Writer, PackedDna};
+///
+/// let seq = PackedDna::from_str("ACGTACGTAC").unwrap();
+/// let mut writer = Writer::new(Vec::new()).line_width(4);
+/// writer.write_seq("seq1", Some("example"), seq.as_slice()).unwrap();
+/// let text = String::from_utf8(writer.into_inner()).unwrap();
+/// assert_eq!(text, ">seq1 example\nACGT\nACGT\nAC\n");
+/// ```
+#[derive(Debug)]
+pub struct Writer<W> {
+    inner: W,
+    line_width: usize,
+    soft_mask: bool,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a writer over `inner` that wraps sequences at
+    /// [`DEFAULT_LINE_WIDTH`] and writes soft-masked positions in
+    /// lowercase.
+    pub fn new(inner: W) -> Writer<W> {
+        Writer {
+            inner,
+            line_width: DEFAULT_LINE_WIDTH,
+            soft_mask: true,
+        }
+    }
+
+    /// Sets the number of nucleotides per sequence line. A width of zero
+    /// writes every sequence on a single line.
+    pub fn line_width(mut self, width: usize) -> Writer<W> {
+        self.line_width = width;
+        self
+    }
+
+    /// Sets whether the soft-masked positions of a [`MaskedPackedDna`] are
+    /// written in lowercase. When disabled, every nucleotide is upper case.
+    pub fn soft_mask(mut self, yes: bool) -> Writer<W> {
+        self.soft_mask = yes;
+        self
+    }
+
+    /// Writes `record`.
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        self.write_seq(
+            &record.id,
+            record.description.as_deref(),
+            record.seq.as_slice(),
+        )
+    }
+
+    /// Writes a record with the given header and nucleotides.
+    pub fn write_seq(
+        &mut self,
+        id: &str,
+        description: Option<&str>,
+        seq: PackedDnaSlice<'_>,
+    ) -> io::Result<()> {
+        self.write_header(id, description)?;
+        let mut lines = Lines::new(&mut self.inner, self.line_width);
+        seq.for_each_ascii(|chunk| lines.write(chunk))?;
+        lines.finish()
+    }
+
+    /// Writes a record with the given header and a sequence that may hold
+    /// `N` runs and soft-masked regions.
+    pub fn write_masked(
+        &mut self,
+        id: &str,
+        description: Option<&str>,
+        seq: &MaskedPackedDna,
+    ) -> io::Result<()> {
+        self.write_header(id, description)?;
+        let mut lines = Lines::new(&mut self.inner, self.line_width);
+        seq.for_each_ascii(self.soft_mask, |chunk| lines.write(chunk))?;
+        lines.finish()
+    }
+
+    /// Writes a record with the given header and IUPAC symbols.
+    pub fn write_iupac(
+        &mut self,
+        id: &str,
+        description: Option<&str>,
+        seq: &PackedIupacDna,
+    ) -> io::Result<()> {
+        self.write_header(id, description)?;
+        let mut lines = Lines::new(&mut self.inner, self.line_width);
+        let mut buf = [0u8; 32];
+        let mut count = 0;
+        for symbol in seq.iter() {
+            buf[count] = char::from(symbol) as u8;
+            count += 1;
+            if count == buf.len() {
+                lines.write(&buf)?;
+                count = 0;
+            }
+        }
+        lines.write(&buf[..count])?;
+        lines.finish()
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_header(&mut self, id: &str, description: Option<&str>) -> io::Result<()> {
+        match description {
+            Some(description) => writeln!(self.inner, ">{} {}", id, description),
+            None => writeln!(self.inner, ">{}", id),
+        }
+    }
+}
+
+/// Splits the sequence bytes of one record into lines.
+struct Lines<'a, W> {
+    inner: &'a mut W,
+    width: usize,
+    // nucleotides written to the current line
+    column: usize,
+}
+
+impl<'a, W: Write> Lines<'a, W> {
+    fn new(inner: &'a mut W, width: usize) -> Lines<'a, W> {
+        Lines {
+            inner,
+            width,
+            column: 0,
+        }
+    }
+
+    fn write(&mut self, mut chunk: &[u8]) -> io::Result<()> {
+        if self.width == 0 {
+            self.column += chunk.len();
+            return self.inner.write_all(chunk);
+        }
+        while !chunk.is_empty() {
+            if self.column == self.width {
+                self.inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+            let count = chunk.len().min(self.width - self.column);
+            self.inner.write_all(&chunk[..count])?;
+            self.column += count;
+            chunk = &chunk[count..];
+        }
+        Ok(())
+    }
+
+    /// Ends the last line, if the sequence was not empty.
+    fn finish(self) -> io::Result<()> {
+        if self.column > 0 {
+            self.inner.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fasta::Reader, PackedDna, ParseOptions};
+    use std::str::FromStr;
+
+    const TEXT: &str = "GGCATTACGATCAGGGATCCCTAGCATGCATTTACGACTAGCAGCGGCTATATATCAGCGAT";
+
+    fn written<F: FnOnce(&mut Writer<Vec<u8>>) -> io::Result<()>>(
+        width: usize,
+        write: F,
+    ) -> String {
+        let mut writer = Writer::new(Vec::new()).line_width(width);
+        write(&mut writer).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    // Test to check wrapping at several widths, including widths that
+    // divide the sequence length, and reading the output back
+    #[test]
+    fn test_write_wrapping() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        for width in [0, 1, 7, 31, 32, 33, 60, 62, 100] {
+            let text = written(width, |w| w.write_seq("s", None, dna.as_slice()));
+            let lines: Vec<&str> = text.lines().collect();
+            assert_eq!(lines[0], ">s");
+            assert_eq!(lines[1..].concat(), TEXT);
+            if width > 0 {
+                // every line but the last is full
+                let (last, full) = lines[1..].split_last().unwrap();
+                assert!(full.iter().all(|line| line.len() == width));
+                assert!(!last.is_empty() && last.len() <= width);
+            } else {
+                assert_eq!(lines.len(), 2);
+            }
+            let record = Reader::new(text.as_bytes()).next().unwrap().unwrap();
+            assert_eq!(record.seq, dna);
+        }
+        let empty = written(60, |w| {
+            w.write_seq("e", Some("empty"), PackedDna::new().as_slice())
+        });
+        assert_eq!(empty, ">e empty\n");
+    }
+
+    // Test to check masked and IUPAC output
+    #[test]
+    fn test_write_masked_iupac() {
+        let masked = ParseOptions::new()
+            .parse_masked_with(
+                "ACGTacgtnnNNACGTNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNnnnGT",
+                true,
+            )
+            .unwrap();
+        let text = written(10, |w| w.write_masked("m", None, &masked));
+        assert_eq!(text.lines().skip(1).collect::<String>(), masked.to_string());
+        assert_eq!(text.lines().nth(1), Some("ACGTacgtnn"));
+
+        let mut writer = Writer::new(Vec::new()).line_width(0).soft_mask(false);
+        writer.write_masked("m", None, &masked).unwrap();
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            text,
+            format!(">m\n{}\n", masked.to_string().to_ascii_uppercase())
+        );
+
+        let iupac = PackedIupacDna::from_str("ACGTRYKMSWBDHVN-ACGTRYKMSWBDHVN-ACGTR").unwrap();
+        let text = written(16, |w| w.write_iupac("i", Some("ambiguous"), &iupac));
+        assert_eq!(
+            text,
+            ">i ambiguous\nACGTRYKMSWBDHVN-\nACGTRYKMSWBDHVN-\nACGTR\n"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Reading and writing packed sequences and their qualities as FASTQ.
+//!
+//! ```
+//! use dna::fastq::{Reader, Writer};
+//!
+//! let text = "@read1 lane 1\nACGT\n+\nII#5\n";
+//! let record = Reader::new(text.as_bytes()).next().unwrap().unwrap();
+//! assert_eq!(record.id, "read1");
+//! assert_eq!(record.seq.to_string(), "ACGT");
+//! assert_eq!(record.qual.iter().collect::<Vec<_>>(), [40, 40, 2, 20]);
+//!
+//! let mut writer = Writer::new(Vec::new());
+//! writer.write_record(&record).unwrap();
+//! assert_eq!(writer.into_inner(), text.as_bytes());
+//! ```
+
+use std::io;
+
+mod reader;
+mod writer;
+
+pub use reader::{MaskedRecord, Reader, Record};
+pub use writer::Writer;
+
+/// How quality scores are written as characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QualityEncoding {
+    /// Phred+33, used by Sanger and every current Illumina pipeline.
+    Phred33,
+    /// Phred+64, used by Illumina pipelines before version 1.8.
+    Phred64,
+}
+
+impl QualityEncoding {
+    /// Returns the character code of a score of zero.
+    pub fn offset(self) -> u8 {
+        match self {
+            QualityEncoding::Phred33 => 33,
+            QualityEncoding::Phred64 => 64,
+        }
+    }
+
+    /// Returns the highest score that can be written, the one encoded as
+    /// `~`.
+    pub fn max_score(self) -> u8 {
+        b'~' - self.offset()
+    }
+
+    /// Decodes a quality character, or returns `None` if it is out of
+    /// range for this encoding.
+    pub(crate) fn decode(self, c: u8) -> Option<u8> {
+        match c {
+            b'!'..=b'~' => c.checked_sub(self.offset()),
+            _ => None,
+        }
+    }
+}
+
+/// An error that can occur while reading FASTQ.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Reading from the underlying source failed.
+    #[error("failed to read FASTQ: {0}")]
+    Io(#[from] io::Error),
+    /// A record did not start with an `@` header.
+    #[error("line {line}: expected a '@' header")]
+    MissingHeader {
+        /// The offending line, counting from 1.
+        line: usize,
+    },
+    /// A header had no identifier after the `@`.
+    #[error("line {line}: header has no identifier")]
+    EmptyId {
+        /// The offending line, counting from 1.
+        line: usize,
+    },
+    /// The line after the sequence did not start with `+`.
+    #[error("line {line}: expected a '+' separator")]
+    MissingSeparator {
+        /// The offending line, counting from 1.
+        line: usize,
+    },
+    /// The input ended in the middle of a record.
+    #[error("line {line}: record is truncated")]
+    Truncated {
+        /// The line the missing part was expected on, counting from 1.
+        line: usize,
+    },
+    /// A sequence line contained a character that is not a nucleotide.
+    #[error("line {line}, column {column}: invalid nucleotide {symbol:?}")]
+    InvalidSymbol {
+        /// The offending line, counting from 1.
+        line: usize,
+        /// The character position within the line, counting from 1.
+        column: usize,
+        /// The offending character.
+        symbol: char,
+    },
+    /// A quality line contained a character outside the range of the
+    /// quality encoding.
+    #[error("line {line}, column {column}: invalid quality {symbol:?}")]
+    InvalidQuality {
+        /// The offending line, counting from 1.
+        line: usize,
+        /// The character position within the line, counting from 1.
+        column: usize,
+        /// The offending character.
+        symbol: char,
+    },
+    /// The quality line was not as long as the sequence.
+    #[error("line {line}: {qual_len} qualities for {seq_len} nucleotides")]
+    LengthMismatch {
+        /// The quality line, counting from 1.
+        line: usize,
+        /// The number of nucleotides.
+        seq_len: usize,
+        /// The number of qualities.
+        qual_len: usize,
+    },
+}
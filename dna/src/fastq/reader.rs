@@ -0,0 +1,411 @@
+//! A streaming FASTQ reader.
+
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+use super::{Error, QualityEncoding};
+use crate::{
+    compression, fasta::split_header, MaskedNuc, MaskedPackedDna, Nuc, PackedDna, PackedDnaBuilder,
+    PackedQual,
+};
+
+/// A single FASTQ record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Record {
+    /// The identifier: the header up to the first whitespace.
+    pub id: String,
+    /// The rest of the header after the identifier, if any.
+    pub description: Option<String>,
+    /// The sequence.
+    pub seq: PackedDna,
+    /// The Phred quality of every nucleotide of `seq`.
+    pub qual: PackedQual,
+}
+
+/// A single FASTQ record whose sequence may hold `N` no-calls, read by
+/// [`Reader::next_masked`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaskedRecord {
+    /// The identifier: the header up to the first whitespace.
+    pub id: String,
+    /// The rest of the header after the identifier, if any.
+    pub description: Option<String>,
+    /// The sequence, with its no-calls as `N` runs.
+    pub seq: MaskedPackedDna,
+    /// The Phred quality of every position of `seq`, `N` included.
+    pub qual: PackedQual,
+}
+
+/// A streaming reader that yields one [`Record`] at a time from FASTQ
+/// text.
+///
+/// Every record takes exactly four lines: an `@` header, the sequence, a
+/// `+` separator and the qualities, which must be as many as the
+/// nucleotides. Line endings may be `\n` or `\r\n` and blank lines between
+/// records are skipped. Malformed input is reported as an [`Error`] naming
+/// the line it was found on, after which the iterator ends.
+///
+/// The iterator yields [`Record`]s, which only hold A, C, G and T.
+/// Sequencers report bases they could not call as `N`; read files that may
+/// hold them with [`Reader::next_masked`].
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+    buf: String,
+    // number of lines read so far
+    line: usize,
+    encoding: QualityEncoding,
+    binned: bool,
+    done: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a reader over `inner` that decodes Phred+33 qualities and
+    /// keeps them exactly.
+    pub fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            buf: String::new(),
+            line: 0,
+            encoding: QualityEncoding::Phred33,
+            binned: false,
+            done: false,
+        }
+    }
+
+    /// Sets how the quality characters are decoded.
+    pub fn encoding(mut self, encoding: QualityEncoding) -> Reader<R> {
+        self.encoding = encoding;
+        self
+    }
+
+    /// When enabled, qualities are binned to the Illumina levels as they
+    /// are read, see [`PackedQual`].
+    pub fn binning(mut self, yes: bool) -> Reader<R> {
+        self.binned = yes;
+        self
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the next line into `buf` without its line ending, returning
+    /// `false` at the end of the input.
+    fn read_line(&mut self) -> Result<bool, Error> {
+        self.buf.clear();
+        if self.inner.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        let len = self.buf.trim_end_matches(&['\r', '\n'][..]).len();
+        self.buf.truncate(len);
+        Ok(true)
+    }
+
+    /// Reads the next line of the current record, failing if the input
+    /// ends first.
+    fn expect_line(&mut self) -> Result<(), Error> {
+        if self.read_line()? {
+            Ok(())
+        } else {
+            Err(Error::Truncated {
+                line: self.line + 1,
+            })
+        }
+    }
+
+    /// Reads the next header, returning its identifier and description, or
+    /// `None` at the end of the input.
+    fn read_header(&mut self) -> Result<Option<(String, Option<String>)>, Error> {
+        loop {
+            if !self.read_line()? {
+                return Ok(None);
+            }
+            if !self.buf.trim().is_empty() {
+                break;
+            }
+        }
+        let header = self
+            .buf
+            .strip_prefix('@')
+            .ok_or(Error::MissingHeader { line: self.line })?;
+        let (id, description) = split_header(header);
+        if id.is_empty() {
+            return Err(Error::EmptyId { line: self.line });
+        }
+        Ok(Some((id, description)))
+    }
+
+    /// Reads the sequence line and feeds every character to `push`, which
+    /// returns whether the character is a valid symbol.
+    fn read_seq<F: FnMut(char) -> bool>(&mut self, mut push: F) -> Result<(), Error> {
+        self.expect_line()?;
+        for (idx, c) in self.buf.trim_end().chars().enumerate() {
+            if !push(c) {
+                return Err(Error::InvalidSymbol {
+                    line: self.line,
+                    column: idx + 1,
+                    symbol: c,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the separator and the quality lines, checking there is a
+    /// score for each of the `seq_len` positions.
+    fn read_qual(&mut self, seq_len: usize) -> Result<PackedQual, Error> {
+        self.expect_line()?;
+        if !self.buf.starts_with('+') {
+            return Err(Error::MissingSeparator { line: self.line });
+        }
+
+        self.expect_line()?;
+        let mut qual = if self.binned {
+            PackedQual::new_binned()
+        } else {
+            PackedQual::new()
+        };
+        for (idx, c) in self.buf.trim_end().chars().enumerate() {
+            let score = u8::try_from(c)
+                .ok()
+                .and_then(|c| self.encoding.decode(c))
+                .ok_or(Error::InvalidQuality {
+                    line: self.line,
+                    column: idx + 1,
+                    symbol: c,
+                })?;
+            qual.push(score);
+        }
+        if qual.len() != seq_len {
+            return Err(Error::LengthMismatch {
+                line: self.line,
+                seq_len,
+                qual_len: qual.len(),
+            });
+        }
+        Ok(qual)
+    }
+
+    /// Reads the next record, or `None` at the end of the input.
+    fn read_record(&mut self) -> Result<Option<Record>, Error> {
+        let (id, description) = match self.read_header()? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let mut builder = PackedDnaBuilder::new();
+        self.read_seq(|c| match Nuc::try_from(c) {
+            Ok(nuc) => {
+                // a line in memory can never overflow the length
+                builder.push(nuc).expect("read fits in memory");
+                true
+            }
+            Err(_) => false,
+        })?;
+        let seq = builder.build();
+        let qual = self.read_qual(seq.len())?;
+        Ok(Some(Record {
+            id,
+            description,
+            seq,
+            qual,
+        }))
+    }
+
+    /// Reads the next record keeping its no-calls, or `None` at the end of
+    /// the input.
+    fn read_masked_record(&mut self) -> Result<Option<MaskedRecord>, Error> {
+        let (id, description) = match self.read_header()? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let mut seq = MaskedPackedDna::new();
+        self.read_seq(|c| match MaskedNuc::try_from(c) {
+            Ok(symbol) => {
+                seq.push(symbol);
+                true
+            }
+            Err(_) => false,
+        })?;
+        let qual = self.read_qual(seq.len())?;
+        Ok(Some(MaskedRecord {
+            id,
+            description,
+            seq,
+            qual,
+        }))
+    }
+
+    /// Reads the next record as a [`MaskedRecord`], accepting `N` no-calls
+    /// in the sequence, or returns `None` at the end of the input. Records
+    /// read this way and through the iterator may be mixed freely, and
+    /// errors end both.
+    ///
+    /// ```
+    /// use dna::fastq::Reader;
+    ///
+    /// let mut reader = Reader::new("@r1\nACNNT\n+\nII##I\n".as_bytes());
+    /// let record = reader.next_masked().unwrap().unwrap();
+    /// assert_eq!(record.seq.n_runs(), &[2..4]);
+    /// assert_eq!(record.qual.get(2), Some(2));
+    /// assert!(reader.next_masked().is_none());
+    /// ```
+    pub fn next_masked(&mut self) -> Option<Result<MaskedRecord, Error>> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_masked_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+impl<R: Read> Reader<BufReader<R>> {
+    /// Creates a reader over an unbuffered source, such as a
+    /// [`File`](std::fs::File), wrapping it in a [`BufReader`].
+    pub fn from_reader(inner: R) -> Reader<BufReader<R>> {
+        Reader::new(BufReader::new(inner))
+    }
+}
+
+impl Reader<compression::Reader<BufReader<File>>> {
+    /// Opens the FASTQ file at `path`, decompressing it if it is gzip or
+    /// BGZF.
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+    ) -> io::Result<Reader<compression::Reader<BufReader<File>>>> {
+        Ok(Reader::new(compression::Reader::from_path(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn read_all(text: &str) -> Result<Vec<Record>, Error> {
+        Reader::new(text.as_bytes()).collect()
+    }
+
+    // Test to check reading records in both encodings, with CRLF endings,
+    // blank lines, repeated ids on the separator and binning
+    #[test]
+    fn test_read_records() {
+        let text = "@r1 desc\r\nACGT\r\n+r1\r\n!+5I\r\n\n@r2\nGG\n+\n~~\n\n";
+        let records = read_all(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, "r1");
+        assert_eq!(records[0].description.as_deref(), Some("desc"));
+        assert_eq!(records[0].seq, PackedDna::from_str("ACGT").unwrap());
+        assert_eq!(records[0].qual.iter().collect::<Vec<_>>(), [0, 10, 20, 40]);
+        assert_eq!(records[1].qual.iter().collect::<Vec<_>>(), [93, 93]);
+
+        let old = "@r\nACG\n+\n@Jh\n";
+        let record = Reader::new(old.as_bytes())
+            .encoding(QualityEncoding::Phred64)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.qual.iter().collect::<Vec<_>>(), [0, 10, 40]);
+
+        let binned = Reader::new(text.as_bytes())
+            .binning(true)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(binned.qual.is_binned());
+        assert_eq!(binned.qual.iter().collect::<Vec<_>>(), [0, 15, 22, 40]);
+        assert!(read_all("").unwrap().is_empty());
+    }
+
+    // Test to check that malformed records are reported with their line
+    // numbers
+    #[test]
+    fn test_read_errors() {
+        assert!(matches!(
+            read_all(">r\nACGT\n+\nIIII\n"),
+            Err(Error::MissingHeader { line: 1 })
+        ));
+        assert!(matches!(
+            read_all("@r\nACGT\n-\nIIII\n"),
+            Err(Error::MissingSeparator { line: 3 })
+        ));
+        assert!(matches!(
+            read_all("@r\nACGT\n+\nIII\n"),
+            Err(Error::LengthMismatch {
+                line: 4,
+                seq_len: 4,
+                qual_len: 3
+            })
+        ));
+        assert!(matches!(
+            read_all("@r\nACGT\n+\nIIII\n@s\nAC\n"),
+            Err(Error::Truncated { line: 7 })
+        ));
+        assert!(matches!(
+            read_all("@r\nACNT\n+\nIIII\n"),
+            Err(Error::InvalidSymbol {
+                line: 2,
+                column: 3,
+                symbol: 'N'
+            })
+        ));
+        let mut reader =
+            Reader::new("@r\nACGT\n+\nII5I\n".as_bytes()).encoding(QualityEncoding::Phred64);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::InvalidQuality {
+                line: 4,
+                column: 3,
+                symbol: '5'
+            }))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    // Test to check that masked records keep no-calls with their qualities,
+    // mix with plain records and still reject other symbols
+    #[test]
+    fn test_read_masked() {
+        let text = "@r1\nNACGTn\n+\n#IIII#\n@r2\nACGT\n+\nIIII\n@r3\nNNX\n+\n###\n";
+        let mut reader = Reader::new(text.as_bytes());
+        let record = reader.next_masked().unwrap().unwrap();
+        assert_eq!(record.id, "r1");
+        assert_eq!(record.seq.to_string(), "NACGTN");
+        assert_eq!(record.seq.counts().n(), 2);
+        assert_eq!(record.qual.len(), 6);
+        assert_eq!(reader.next().unwrap().unwrap().seq.to_string(), "ACGT");
+        assert!(matches!(
+            reader.next_masked(),
+            Some(Err(Error::InvalidSymbol {
+                line: 10,
+                column: 3,
+                symbol: 'X'
+            }))
+        ));
+        assert!(reader.next_masked().is_none());
+    }
+}
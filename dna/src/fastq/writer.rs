@@ -0,0 +1,173 @@
+//! A streaming FASTQ writer.
+
+use std::io::{self, Write};
+
+use super::{MaskedRecord, QualityEncoding, Record};
+use crate::{PackedDnaSlice, PackedQual};
+
+/// Writes sequences and their qualities as four line FASTQ records.
+#[derive(Debug)]
+pub struct Writer<W> {
+    inner: W,
+    encoding: QualityEncoding,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a writer over `inner` that encodes qualities as Phred+33.
+    pub fn new(inner: W) -> Writer<W> {
+        Writer {
+            inner,
+            encoding: QualityEncoding::Phred33,
+        }
+    }
+
+    /// Sets how the qualities are encoded.
+    pub fn encoding(mut self, encoding: QualityEncoding) -> Writer<W> {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Writes `record`.
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        self.write_seq(
+            &record.id,
+            record.description.as_deref(),
+            record.seq.as_slice(),
+            &record.qual,
+        )
+    }
+
+    /// Writes a record read by
+    /// [`Reader::next_masked`](super::Reader::next_masked), with its
+    /// no-calls written as `N`.
+    pub fn write_masked_record(&mut self, record: &MaskedRecord) -> io::Result<()> {
+        let seq = &record.seq;
+        self.write_parts(
+            &record.id,
+            record.description.as_deref(),
+            seq.len(),
+            &record.qual,
+            |inner| seq.for_each_ascii(false, |chunk| inner.write_all(chunk)),
+        )
+    }
+
+    /// Writes a record with the given header, nucleotides and qualities.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`], before anything is
+    /// written, if there are not as many qualities as nucleotides or a
+    /// score is too high for the encoding.
+    pub fn write_seq(
+        &mut self,
+        id: &str,
+        description: Option<&str>,
+        seq: PackedDnaSlice<'_>,
+        qual: &PackedQual,
+    ) -> io::Result<()> {
+        self.write_parts(id, description, seq.len(), qual, |inner| {
+            seq.for_each_ascii(|chunk| inner.write_all(chunk))
+        })
+    }
+
+    /// Writes a record whose `len` sequence characters are written by
+    /// `write_seq`, checking the qualities first.
+    fn write_parts<F>(
+        &mut self,
+        id: &str,
+        description: Option<&str>,
+        len: usize,
+        qual: &PackedQual,
+        write_seq: F,
+    ) -> io::Result<()>
+    where
+        F: FnOnce(&mut W) -> io::Result<()>,
+    {
+        if len != qual.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} qualities for {} nucleotides", qual.len(), len),
+            ));
+        }
+        let offset = self.encoding.offset();
+        let max_score = self.encoding.max_score();
+        let mut encoded = Vec::with_capacity(qual.len() + 1);
+        for score in qual.iter() {
+            if score > max_score {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("quality {} cannot be encoded", score),
+                ));
+            }
+            encoded.push(score + offset);
+        }
+        encoded.push(b'\n');
+
+        match description {
+            Some(description) => writeln!(self.inner, "@{} {}", id, description)?,
+            None => writeln!(self.inner, "@{}", id)?,
+        }
+        write_seq(&mut self.inner)?;
+        self.inner.write_all(b"\n+\n")?;
+        self.inner.write_all(&encoded)
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fastq::Reader, PackedDna};
+    use std::str::FromStr;
+
+    // Test to check that written records read back the same in both
+    // encodings, and that invalid records are refused
+    #[test]
+    fn test_write_round_trip() {
+        let seq = PackedDna::from_str("ACGTTGCAACGTTGCAACGTTGCAACGTTGCAACGT").unwrap();
+        let qual: PackedQual = (0..seq.len() as u8).collect();
+        let record = Record {
+            id: "r1".to_string(),
+            description: Some("sample=1".to_string()),
+            seq,
+            qual,
+        };
+        for &encoding in &[QualityEncoding::Phred33, QualityEncoding::Phred64] {
+            let mut writer = Writer::new(Vec::new()).encoding(encoding);
+            writer.write_record(&record).unwrap();
+            writer.write_record(&record).unwrap();
+            let text = writer.into_inner();
+            let records: Vec<Record> = Reader::new(&text[..])
+                .encoding(encoding)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(records, [record.clone(), record.clone()]);
+        }
+
+        let mut writer = Writer::new(Vec::new()).encoding(QualityEncoding::Phred64);
+        let short: PackedQual = vec![30; 3].into_iter().collect();
+        let err = writer
+            .write_seq("r", None, record.seq.slice(..4), &short)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let high: PackedQual = vec![30, 70].into_iter().collect();
+        let err = writer
+            .write_seq("r", None, record.seq.slice(..2), &high)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(writer.into_inner().is_empty());
+
+        let text = "@r1 lane 2\nNACGTN\n+\n#IIII#\n";
+        let masked = Reader::new(text.as_bytes()).next_masked().unwrap().unwrap();
+        let mut writer = Writer::new(Vec::new());
+        writer.write_masked_record(&masked).unwrap();
+        assert_eq!(writer.into_inner(), text.as_bytes());
+    }
+}
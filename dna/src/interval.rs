@@ -0,0 +1,131 @@
+//! Sorted lists of half-open position intervals, used to record runs of
+//! `N` and soft-masked regions alongside a packed sequence.
+
+use std::ops::Range;
+
+/// A sorted list of disjoint, non-adjacent half-open intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Intervals {
+    ranges: Vec<Range<usize>>,
+}
+
+impl Intervals {
+    /// Creates an empty list.
+    pub(crate) fn new() -> Intervals {
+        Intervals::default()
+    }
+
+    /// Returns the intervals in ascending order.
+    pub(crate) fn as_slice(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Appends `range`, which must not start before the last interval
+    /// starts. Overlapping or adjacent intervals are merged so the list
+    /// stays minimal; empty ranges are ignored.
+    pub(crate) fn push(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        if let Some(last) = self.ranges.last_mut() {
+            debug_assert!(range.start >= last.start);
+            if range.start <= last.end {
+                last.end = last.end.max(range.end);
+                return;
+            }
+        }
+        self.ranges.push(range);
+    }
+
+    /// Marks the single position `pos`, which must not be before the start
+    /// of the last interval.
+    pub(crate) fn push_pos(&mut self, pos: usize) {
+        self.push(pos..pos + 1);
+    }
+
+    /// Returns `true` if `pos` lies inside one of the intervals.
+    pub(crate) fn contains(&self, pos: usize) -> bool {
+        // the first interval ending after `pos` is the only candidate
+        let idx = self.ranges.partition_point(|range| range.end <= pos);
+        matches!(self.ranges.get(idx), Some(range) if range.start <= pos)
+    }
+
+    /// Returns the parts of the intervals that overlap `window`, clipped to
+    /// it, in ascending order.
+    pub(crate) fn overlapping(
+        &self,
+        window: Range<usize>,
+    ) -> impl Iterator<Item = Range<usize>> + '_ {
+        let Range { start, end } = window;
+        let first = self.ranges.partition_point(|range| range.end <= start);
+        self.ranges[first..]
+            .iter()
+            .take_while(move |range| range.start < end)
+            .map(move |range| range.start.max(start)..range.end.min(end))
+    }
+
+    /// Returns the number of positions of `window` that are covered.
+    pub(crate) fn covered(&self, window: Range<usize>) -> usize {
+        self.overlapping(window).map(|range| range.len()).sum()
+    }
+
+    /// Returns the intervals overlapping `window`, clipped to it and
+    /// shifted so that `window.start` becomes position zero.
+    pub(crate) fn restrict(&self, window: Range<usize>) -> Intervals {
+        let offset = window.start;
+        Intervals {
+            ranges: self
+                .overlapping(window)
+                .map(|range| range.start - offset..range.end - offset)
+                .collect(),
+        }
+    }
+
+    /// Returns the intervals as they fall on a sequence of length `len`
+    /// read backwards.
+    pub(crate) fn reverse(&self, len: usize) -> Intervals {
+        Intervals {
+            ranges: self
+                .ranges
+                .iter()
+                .rev()
+                .map(|range| len - range.end..len - range.start)
+                .collect(),
+        }
+    }
+}
+
+impl From<Vec<Range<usize>>> for Intervals {
+    /// Builds a list from arbitrary ranges, sorting and merging them.
+    fn from(mut ranges: Vec<Range<usize>>) -> Intervals {
+        ranges.sort_by_key(|range| range.start);
+        let mut intervals = Intervals::new();
+        for range in ranges {
+            intervals.push(range);
+        }
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test to check merging, lookups and window arithmetic
+    #[test]
+    fn test_intervals() {
+        let mut intervals = Intervals::new();
+        intervals.push(2..4);
+        intervals.push(4..6);
+        intervals.push_pos(6);
+        intervals.push(10..12);
+        intervals.push(11..11);
+        assert_eq!(intervals.as_slice(), &[2..7, 10..12]);
+        let covered: Vec<usize> = (0..14).filter(|&pos| intervals.contains(pos)).collect();
+        assert_eq!(covered, vec![2, 3, 4, 5, 6, 10, 11]);
+        assert_eq!(intervals.covered(5..11), 3);
+        assert_eq!(intervals.restrict(5..11).as_slice(), &[0..2, 5..6]);
+        assert_eq!(intervals.reverse(12).as_slice(), &[0..2, 5..10]);
+        assert_eq!(Intervals::from(vec![10..12, 2..5, 4..7]), intervals);
+    }
+}
@@ -0,0 +1,273 @@
+//! Iterators over the nucleotides of packed sequences.
+
+use std::iter::FusedIterator;
+
+use crate::{packed::locate, Nuc, PackedDna, PackedDnaSlice};
+
+/// Decodes the nucleotide at absolute position `pos` of `data`.
+fn nuc_at(data: &[u64], pos: usize) -> Nuc {
+    let (word, shift) = locate(pos);
+    Nuc::from_bits((data[word] >> shift) as u8)
+}
+
+/// A borrowing iterator over the nucleotides of a [`PackedDna`] or
+/// [`PackedDnaSlice`], created by their `iter` methods.
+///
+/// Besides iterating from either end, the iterator can jump forward or
+/// backward in O(1) with [`Iterator::nth`], [`DoubleEndedIterator::nth_back`]
+/// and [`Iterator::skip`], since every position maps directly onto the packed
+/// words.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{Nuc, PackedDna};
+///
+/// let dna = PackedDna::from_str("GATTACA").unwrap();
+/// let mut iter = dna.iter();
+/// assert_eq!(iter.next(), Some(Nuc::G));
+/// assert_eq!(iter.next_back(), Some(Nuc::A));
+/// assert_eq!(iter.nth(2), Some(Nuc::T));
+/// assert_eq!(iter.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    data: &'a [u64],
+    // absolute positions in `data` of the next nucleotide to yield from the
+    // front and one past the next one to yield from the back
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iter<'a> {
+    /// Creates an iterator over the nucleotides of `data` in `front..back`.
+    pub(crate) fn new(data: &'a [u64], front: usize, back: usize) -> Iter<'a> {
+        Iter { data, front, back }
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Nuc;
+
+    fn next(&mut self) -> Option<Nuc> {
+        if self.front == self.back {
+            return None;
+        }
+        let nuc = nuc_at(self.data, self.front);
+        self.front += 1;
+        Some(nuc)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Nuc> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+
+    fn count(self) -> usize {
+        self.len()
+    }
+
+    fn last(mut self) -> Option<Nuc> {
+        self.next_back()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Nuc> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(nuc_at(self.data, self.back))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Nuc> {
+        self.back = self.back.saturating_sub(n).max(self.front);
+        self.next_back()
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+/// An owning iterator over the nucleotides of a [`PackedDna`], created by
+/// its [`IntoIterator`] implementation.
+///
+/// It supports the same O(1) jumps as [`Iter`].
+#[derive(Debug, Clone)]
+pub struct IntoIter {
+    dna: PackedDna,
+    front: usize,
+    back: usize,
+}
+
+impl IntoIter {
+    /// Returns the packed words of the sequence being iterated.
+    fn data(&self) -> &[u64] {
+        self.dna.words()
+    }
+}
+
+impl Iterator for IntoIter {
+    type Item = Nuc;
+
+    fn next(&mut self) -> Option<Nuc> {
+        if self.front == self.back {
+            return None;
+        }
+        let nuc = nuc_at(self.data(), self.front);
+        self.front += 1;
+        Some(nuc)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Nuc> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+
+    fn count(self) -> usize {
+        self.len()
+    }
+
+    fn last(mut self) -> Option<Nuc> {
+        self.next_back()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Nuc> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(nuc_at(self.data(), self.back))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Nuc> {
+        self.back = self.back.saturating_sub(n).max(self.front);
+        self.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl FusedIterator for IntoIter {}
+
+impl IntoIterator for PackedDna {
+    type Item = Nuc;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        let back = self.len();
+        IntoIter {
+            dna: self,
+            front: 0,
+            back,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a PackedDna {
+    type Item = Nuc;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for PackedDnaSlice<'a> {
+    type Item = Nuc;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &PackedDnaSlice<'a> {
+    type Item = Nuc;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{convert::TryFrom, str::FromStr};
+
+    const TEXT: &str = "TTGACCATGGCATCGATCGGATACGATCGATTCAGCTAGCTAGGCTAAATCG";
+
+    fn nucs(text: &str) -> Vec<Nuc> {
+        text.chars().map(|c| Nuc::try_from(c).unwrap()).collect()
+    }
+
+    // Test to check forward, backward and mixed iteration against a plain
+    // vector, for owned, borrowed and sliced sequences
+    #[test]
+    fn test_iter_matches_vec() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        let expected = nucs(TEXT);
+        assert_eq!(dna.iter().collect::<Vec<_>>(), expected);
+        assert_eq!((&dna).into_iter().rev().collect::<Vec<_>>(), {
+            let mut rev = expected.clone();
+            rev.reverse();
+            rev
+        });
+        assert_eq!(dna.clone().into_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            dna.slice(7..40).iter().collect::<Vec<_>>(),
+            expected[7..40].to_vec()
+        );
+
+        let mut iter = dna.slice(3..).iter();
+        let mut model = expected[3..].iter().copied();
+        loop {
+            assert_eq!(iter.len(), model.len());
+            let (a, b) = (iter.next(), model.next());
+            assert_eq!(a, b);
+            assert_eq!(iter.next_back(), model.next_back());
+            if a.is_none() {
+                break;
+            }
+        }
+        // fused
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    // Test to check that jumps land on the same nucleotide a plain vector
+    // iterator would, including jumps past the end
+    #[test]
+    fn test_iter_nth_skip() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        let expected = nucs(TEXT);
+        for n in 0..TEXT.len() + 2 {
+            assert_eq!(dna.iter().nth(n), expected.iter().copied().nth(n));
+            assert_eq!(dna.iter().nth_back(n), expected.iter().copied().nth_back(n));
+            assert_eq!(
+                dna.clone().into_iter().skip(n).collect::<Vec<_>>(),
+                expected.iter().copied().skip(n).collect::<Vec<_>>()
+            );
+            assert_eq!(dna.iter().skip(n).len(), TEXT.len().saturating_sub(n));
+        }
+        let mut iter = dna.iter();
+        assert_eq!(iter.nth(usize::MAX), None);
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(dna.iter().last(), expected.last().copied());
+        assert_eq!(dna.iter().count(), TEXT.len());
+    }
+}
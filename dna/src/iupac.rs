@@ -0,0 +1,456 @@
+//! The IUPAC nucleotide alphabet, including ambiguity codes, and a packed
+//! sequence type that stores it with four bits per symbol.
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    iter::FromIterator,
+    str::FromStr,
+};
+
+use crate::{Nuc, PackedDna, ParseDnaError, ParseNucError};
+
+/// A nucleotide symbol from the IUPAC alphabet: the four nucleotides, the
+/// eleven ambiguity codes and the gap.
+///
+/// Each symbol is represented by the set of nucleotides it may stand for,
+/// one bit per nucleotide (A = 1, C = 2, G = 4, T = 8), so `N` is every
+/// nucleotide and the gap is none of them.
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use dna::{IupacNuc, Nuc};
+///
+/// let r = IupacNuc::try_from('r').unwrap();
+/// assert!(r.matches(Nuc::A) && r.matches(Nuc::G));
+/// assert!(!r.matches(Nuc::C));
+/// assert_eq!(r.complement(), IupacNuc::Y);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IupacNuc {
+    /// Gap (`-`)
+    Gap = 0,
+    /// Adenine
+    A = 1,
+    /// Cytosine
+    C = 2,
+    /// Amino: A or C
+    M = 3,
+    /// Guanine
+    G = 4,
+    /// Purine: A or G
+    R = 5,
+    /// Strong: C or G
+    S = 6,
+    /// Not T: A, C or G
+    V = 7,
+    /// Thymine
+    T = 8,
+    /// Weak: A or T
+    W = 9,
+    /// Pyrimidine: C or T
+    Y = 10,
+    /// Not G: A, C or T
+    H = 11,
+    /// Keto: G or T
+    K = 12,
+    /// Not C: A, G or T
+    D = 13,
+    /// Not A: C, G or T
+    B = 14,
+    /// Any nucleotide
+    N = 15,
+}
+
+// every symbol, indexed by its bit set
+const SYMBOLS: [IupacNuc; 16] = [
+    IupacNuc::Gap,
+    IupacNuc::A,
+    IupacNuc::C,
+    IupacNuc::M,
+    IupacNuc::G,
+    IupacNuc::R,
+    IupacNuc::S,
+    IupacNuc::V,
+    IupacNuc::T,
+    IupacNuc::W,
+    IupacNuc::Y,
+    IupacNuc::H,
+    IupacNuc::K,
+    IupacNuc::D,
+    IupacNuc::B,
+    IupacNuc::N,
+];
+
+impl IupacNuc {
+    /// Returns the four bit set of nucleotides this symbol stands for.
+    pub(crate) fn bits(self) -> u8 {
+        self as u8
+    }
+
+    /// Converts a four bit set of nucleotides back into a symbol. Only the
+    /// low four bits of `bits` are looked at.
+    pub(crate) fn from_bits(bits: u8) -> IupacNuc {
+        SYMBOLS[usize::from(bits & 0xf)]
+    }
+
+    /// Returns `true` if this symbol may stand for `nuc`.
+    pub fn matches(self, nuc: Nuc) -> bool {
+        self.bits() & IupacNuc::from(nuc).bits() != 0
+    }
+
+    /// Returns `true` unless the symbol stands for exactly one nucleotide.
+    /// The gap counts as ambiguous.
+    pub fn is_ambiguous(self) -> bool {
+        self.to_nuc().is_none()
+    }
+
+    /// Returns the nucleotide this symbol stands for if it is unambiguous.
+    pub fn to_nuc(self) -> Option<Nuc> {
+        match self {
+            IupacNuc::A => Some(Nuc::A),
+            IupacNuc::C => Some(Nuc::C),
+            IupacNuc::G => Some(Nuc::G),
+            IupacNuc::T => Some(Nuc::T),
+            _ => None,
+        }
+    }
+
+    /// Returns the complementary symbol, e.g. `R` (A or G) becomes `Y`
+    /// (T or C). `N`, `S`, `W` and the gap are their own complements.
+    pub fn complement(self) -> IupacNuc {
+        // complementing swaps the A and T bits and the C and G bits, which
+        // reverses the four bit set
+        let bits = self.bits();
+        IupacNuc::from_bits((bits & 1) << 3 | (bits & 2) << 1 | (bits & 4) >> 1 | (bits & 8) >> 3)
+    }
+}
+
+impl From<Nuc> for IupacNuc {
+    fn from(nuc: Nuc) -> IupacNuc {
+        match nuc {
+            Nuc::A => IupacNuc::A,
+            Nuc::C => IupacNuc::C,
+            Nuc::G => IupacNuc::G,
+            Nuc::T => IupacNuc::T,
+        }
+    }
+}
+
+impl From<IupacNuc> for char {
+    fn from(symbol: IupacNuc) -> char {
+        b"-ACMGRSVTWYHKDBN"[usize::from(symbol.bits())] as char
+    }
+}
+
+impl Display for IupacNuc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+impl TryFrom<char> for IupacNuc {
+    type Error = ParseNucError<char>;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        let symbol = match value.to_ascii_uppercase() {
+            '-' | '.' => IupacNuc::Gap,
+            'A' => IupacNuc::A,
+            'C' => IupacNuc::C,
+            'M' => IupacNuc::M,
+            'G' => IupacNuc::G,
+            'R' => IupacNuc::R,
+            'S' => IupacNuc::S,
+            'V' => IupacNuc::V,
+            'T' => IupacNuc::T,
+            'W' => IupacNuc::W,
+            'Y' => IupacNuc::Y,
+            'H' => IupacNuc::H,
+            'K' => IupacNuc::K,
+            'D' => IupacNuc::D,
+            'B' => IupacNuc::B,
+            'N' => IupacNuc::N,
+            _ => return Err(ParseNucError(value)),
+        };
+        Ok(symbol)
+    }
+}
+
+impl FromStr for IupacNuc {
+    type Err = ParseNucError<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => IupacNuc::try_from(c).map_err(|_| ParseNucError(s.to_string())),
+            _ => Err(ParseNucError(s.to_string())),
+        }
+    }
+}
+
+/// A DNA sequence over the full IUPAC alphabet, stored with four bits per
+/// symbol (two symbols per byte).
+///
+/// Sequences that only contain A, C, G and T convert losslessly to and from
+/// the more compact [`PackedDna`].
+///
+/// ```
+/// use std::{convert::TryFrom, str::FromStr};
+/// use dna::{IupacNuc, PackedDna, PackedIupacDna};
+///
+/// let seq = PackedIupacDna::from_str("ACGTNNRY").unwrap();
+/// assert_eq!(seq.get(4), Some(IupacNuc::N));
+/// assert!(PackedDna::try_from(&seq).is_err());
+///
+/// let dna = PackedDna::from_str("ACGT").unwrap();
+/// let iupac = PackedIupacDna::from(&dna);
+/// assert_eq!(PackedDna::try_from(&iupac).unwrap(), dna);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PackedIupacDna {
+    // two symbols per byte, the first one in the low four bits; the high
+    // bits of the last byte are zero when the length is odd
+    data: Vec<u8>,
+    // number of symbols stored
+    len: usize,
+}
+
+impl PackedIupacDna {
+    /// Creates an empty sequence.
+    pub fn new() -> PackedIupacDna {
+        PackedIupacDna::default()
+    }
+
+    /// Creates an empty sequence with room for at least `capacity` symbols
+    /// before reallocating.
+    pub fn with_capacity(capacity: usize) -> PackedIupacDna {
+        PackedIupacDna {
+            data: Vec::with_capacity(capacity / 2 + 1),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of symbols stored in the sequence.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the sequence holds no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the symbol stored at `idx`, or `None` if `idx` is out of
+    /// bounds.
+    pub fn get(&self, idx: usize) -> Option<IupacNuc> {
+        if idx >= self.len {
+            return None;
+        }
+        Some(IupacNuc::from_bits(self.data[idx / 2] >> ((idx & 1) * 4)))
+    }
+
+    /// Appends a symbol to the end of the sequence.
+    pub fn push(&mut self, symbol: IupacNuc) {
+        if self.len & 1 == 0 {
+            self.data.push(symbol.bits());
+        } else {
+            let last = self.data.len() - 1;
+            self.data[last] |= symbol.bits() << 4;
+        }
+        self.len += 1;
+    }
+
+    /// Returns an iterator over the symbols of the sequence.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = IupacNuc> + ExactSizeIterator + '_ {
+        (0..self.len).map(move |idx| IupacNuc::from_bits(self.data[idx / 2] >> ((idx & 1) * 4)))
+    }
+
+    /// Returns `true` if every symbol is one of A, C, G or T, i.e. the
+    /// sequence converts to a [`PackedDna`] without loss.
+    pub fn is_unambiguous(&self) -> bool {
+        self.iter().all(|symbol| !symbol.is_ambiguous())
+    }
+
+    /// Returns the reverse complement of the sequence.
+    pub fn reverse_complement(&self) -> PackedIupacDna {
+        self.iter().rev().map(IupacNuc::complement).collect()
+    }
+}
+
+impl FromIterator<IupacNuc> for PackedIupacDna {
+    fn from_iter<I: IntoIterator<Item = IupacNuc>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut seq = PackedIupacDna::with_capacity(iter.size_hint().0);
+        for symbol in iter {
+            seq.push(symbol);
+        }
+        seq
+    }
+}
+
+/// Parses a case insensitive string of IUPAC symbols, reporting the first
+/// invalid character the same way [`PackedDna::from_str`] does.
+impl FromStr for PackedIupacDna {
+    type Err = ParseDnaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseDnaError::Empty);
+        }
+        let mut seq = PackedIupacDna::with_capacity(s.len());
+        for (offset, c) in s.char_indices() {
+            let symbol = IupacNuc::try_from(c)
+                .map_err(|_| ParseDnaError::InvalidSymbol { offset, symbol: c })?;
+            seq.push(symbol);
+        }
+        Ok(seq)
+    }
+}
+
+impl Display for PackedIupacDna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = self.iter().map(char::from).collect();
+        f.write_str(&text)
+    }
+}
+
+impl From<&PackedDna> for PackedIupacDna {
+    fn from(dna: &PackedDna) -> PackedIupacDna {
+        dna.iter().map(IupacNuc::from).collect()
+    }
+}
+
+impl From<PackedDna> for PackedIupacDna {
+    fn from(dna: PackedDna) -> PackedIupacDna {
+        PackedIupacDna::from(&dna)
+    }
+}
+
+/// An error returned when converting an IUPAC sequence that contains an
+/// ambiguity code or gap into a [`PackedDna`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("ambiguous symbol {symbol} at position {position}")]
+pub struct AmbiguousNucError {
+    /// Position of the first ambiguous symbol in the sequence.
+    pub position: usize,
+    /// The ambiguous symbol.
+    pub symbol: IupacNuc,
+}
+
+impl TryFrom<&PackedIupacDna> for PackedDna {
+    type Error = AmbiguousNucError;
+
+    fn try_from(seq: &PackedIupacDna) -> Result<Self, Self::Error> {
+        let mut dna = PackedDna::with_capacity(seq.len());
+        for (position, symbol) in seq.iter().enumerate() {
+            match symbol.to_nuc() {
+                Some(nuc) => dna.push(nuc),
+                None => return Err(AmbiguousNucError { position, symbol }),
+            }
+        }
+        Ok(dna)
+    }
+}
+
+impl TryFrom<PackedIupacDna> for PackedDna {
+    type Error = AmbiguousNucError;
+
+    fn try_from(seq: PackedIupacDna) -> Result<Self, Self::Error> {
+        PackedDna::try_from(&seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALPHABET: &str = "-ACMGRSVTWYHKDBN";
+
+    // Test to check that every symbol round trips through its character in
+    // both cases and that the bit sets match the documented meanings
+    #[test]
+    fn test_iupac_symbols() {
+        for (bits, c) in ALPHABET.chars().enumerate() {
+            let symbol = IupacNuc::try_from(c).unwrap();
+            assert_eq!(symbol.bits() as usize, bits);
+            assert_eq!(char::from(symbol), c);
+            assert_eq!(IupacNuc::try_from(c.to_ascii_lowercase()).unwrap(), symbol);
+            assert_eq!(IupacNuc::from_str(&c.to_string()).unwrap(), symbol);
+        }
+        assert_eq!(IupacNuc::try_from('.').unwrap(), IupacNuc::Gap);
+        assert!(IupacNuc::try_from('U').is_err());
+        assert!(IupacNuc::from_str("AC").is_err());
+        assert!(IupacNuc::from_str("").is_err());
+
+        assert!(IupacNuc::N.matches(Nuc::T));
+        assert!(IupacNuc::Y.matches(Nuc::C) && IupacNuc::Y.matches(Nuc::T));
+        assert!(!IupacNuc::Y.matches(Nuc::A) && !IupacNuc::Y.matches(Nuc::G));
+        assert!(!IupacNuc::Gap.matches(Nuc::A));
+        assert!(IupacNuc::D.matches(Nuc::G) && !IupacNuc::D.matches(Nuc::C));
+        assert_eq!(IupacNuc::C.to_nuc(), Some(Nuc::C));
+        assert!(IupacNuc::N.is_ambiguous() && !IupacNuc::G.is_ambiguous());
+    }
+
+    // Test to check complements: unambiguous symbols follow `Nuc`, and a
+    // complemented code matches exactly the complemented nucleotides
+    #[test]
+    fn test_iupac_complement() {
+        for symbol in SYMBOLS.iter().copied() {
+            assert_eq!(symbol.complement().complement(), symbol);
+            for nuc in [Nuc::A, Nuc::C, Nuc::G, Nuc::T] {
+                assert_eq!(
+                    symbol.complement().matches(nuc.complement()),
+                    symbol.matches(nuc)
+                );
+            }
+        }
+        assert_eq!(IupacNuc::B.complement(), IupacNuc::V);
+        assert_eq!(IupacNuc::K.complement(), IupacNuc::M);
+        assert_eq!(IupacNuc::N.complement(), IupacNuc::N);
+    }
+
+    // Test to check the packed sequence and its conversions
+    #[test]
+    fn test_packed_iupac() {
+        let text = "ACGTNNNNRYKM-SWBDHVacgt";
+        let seq = PackedIupacDna::from_str(text).unwrap();
+        assert_eq!(seq.len(), text.len());
+        assert_eq!(seq.to_string(), text.to_ascii_uppercase());
+        assert_eq!(seq.get(text.len()), None);
+        assert_eq!(seq.reverse_complement().reverse_complement(), seq);
+        assert_eq!(
+            PackedIupacDna::from_str("AAGRN")
+                .unwrap()
+                .reverse_complement()
+                .to_string(),
+            "NYCTT"
+        );
+        assert_eq!(
+            PackedDna::try_from(&seq),
+            Err(AmbiguousNucError {
+                position: 4,
+                symbol: IupacNuc::N
+            })
+        );
+        assert_eq!(
+            PackedIupacDna::from_str("ACXGT"),
+            Err(ParseDnaError::InvalidSymbol {
+                offset: 2,
+                symbol: 'X'
+            })
+        );
+        for len in 0..9 {
+            let dna = PackedDna::from_iter(
+                "GATTACAGC"
+                    .chars()
+                    .take(len)
+                    .map(|c| Nuc::try_from(c).unwrap()),
+            );
+            let iupac = PackedIupacDna::from(&dna);
+            assert!(iupac.is_unambiguous());
+            assert_eq!(iupac.len(), len);
+            assert_eq!(iupac.to_string(), dna.to_string());
+            assert_eq!(PackedDna::try_from(iupac).unwrap(), dna);
+        }
+    }
+}
@@ -0,0 +1,573 @@
+//! [`MaskedPackedDna`], a two bit packed sequence that also records runs of
+//! `N` and soft-masked regions, the way UCSC `.2bit` files do.
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    iter::FromIterator,
+    ops::{Range, RangeBounds},
+    str::{self, FromStr},
+};
+
+use crate::{
+    interval::Intervals, slice::resolve_range, AmbiguousNucError, Iter, IupacNuc, Nuc, NucCounts,
+    PackedDna, PackedIupacDna, ParseDnaError, ParseNucError, ParseOptions,
+};
+
+/// A single position of a [`MaskedPackedDna`]: either a nucleotide or an
+/// unknown base (`N`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaskedNuc {
+    /// A known nucleotide.
+    Nuc(Nuc),
+    /// An unknown base.
+    N,
+}
+
+impl MaskedNuc {
+    /// Returns the nucleotide, or `None` for `N`.
+    pub fn nuc(self) -> Option<Nuc> {
+        match self {
+            MaskedNuc::Nuc(nuc) => Some(nuc),
+            MaskedNuc::N => None,
+        }
+    }
+
+    /// Returns the complement; `N` is its own complement.
+    pub fn complement(self) -> MaskedNuc {
+        match self {
+            MaskedNuc::Nuc(nuc) => MaskedNuc::Nuc(nuc.complement()),
+            MaskedNuc::N => MaskedNuc::N,
+        }
+    }
+}
+
+impl From<Nuc> for MaskedNuc {
+    fn from(nuc: Nuc) -> MaskedNuc {
+        MaskedNuc::Nuc(nuc)
+    }
+}
+
+/// Reads `N` and the nucleotides case insensitively.
+impl TryFrom<char> for MaskedNuc {
+    type Error = ParseNucError<char>;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        if c == 'N' || c == 'n' {
+            Ok(MaskedNuc::N)
+        } else {
+            Nuc::try_from(c).map(MaskedNuc::Nuc)
+        }
+    }
+}
+
+impl From<MaskedNuc> for IupacNuc {
+    fn from(symbol: MaskedNuc) -> IupacNuc {
+        match symbol {
+            MaskedNuc::Nuc(nuc) => IupacNuc::from(nuc),
+            MaskedNuc::N => IupacNuc::N,
+        }
+    }
+}
+
+impl From<MaskedNuc> for char {
+    fn from(symbol: MaskedNuc) -> char {
+        match symbol {
+            MaskedNuc::Nuc(nuc) => char::from(nuc),
+            MaskedNuc::N => 'N',
+        }
+    }
+}
+
+impl Display for MaskedNuc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+/// A DNA sequence that may contain unknown bases (`N`), as found in real
+/// assemblies.
+///
+/// The nucleotides keep their two bit packing in a [`PackedDna`] and the
+/// `N` positions are recorded separately as a sorted list of runs, so long
+/// stretches of `N` cost a single interval rather than widening every base
+/// to four bits like [`PackedIupacDna`] does.
+///
+/// Soft-masked positions, written in lowercase in reference FASTA, can be
+/// kept the same way by parsing with [`ParseOptions::parse_masked_with`].
+/// The mask is independent of the bases and of the `N` runs, and is written
+/// back out as lowercase when formatting.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{MaskedNuc, MaskedPackedDna, Nuc, ParseOptions};
+///
+/// let seq = MaskedPackedDna::from_str("ACNNNNGT").unwrap();
+/// assert_eq!(seq.get(1), Some(MaskedNuc::Nuc(Nuc::C)));
+/// assert_eq!(seq.get(2), Some(MaskedNuc::N));
+/// assert_eq!(seq.n_runs(), &[2..6]);
+/// assert_eq!(seq.counts().n(), 4);
+/// assert_eq!(seq.to_string(), "ACNNNNGT");
+///
+/// let seq = ParseOptions::new().parse_masked_with("ACgtnnGT", true).unwrap();
+/// assert!(seq.is_masked(2));
+/// assert_eq!(seq.masked_intervals(), &[2..6]);
+/// assert_eq!(seq.to_string(), "ACgtnnGT");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MaskedPackedDna {
+    // the nucleotides; positions inside an `N` run always hold A so that
+    // equal sequences have equal packed words
+    seq: PackedDna,
+    // runs of `N`
+    n_runs: Intervals,
+    // soft-masked (lowercase) regions
+    soft_mask: Intervals,
+}
+
+impl MaskedPackedDna {
+    /// Creates an empty sequence.
+    pub fn new() -> MaskedPackedDna {
+        MaskedPackedDna::default()
+    }
+
+    /// Assembles a sequence from its nucleotides, `N` runs and soft mask.
+    /// Whatever `seq` holds under the `N` runs is replaced with A.
+    pub(crate) fn from_parts(
+        mut seq: PackedDna,
+        n_runs: Intervals,
+        soft_mask: Intervals,
+    ) -> MaskedPackedDna {
+        for run in n_runs.as_slice() {
+            seq.clear_range(run.clone());
+        }
+        MaskedPackedDna {
+            seq,
+            n_runs,
+            soft_mask,
+        }
+    }
+
+    /// Returns the number of positions in the sequence, `N` included.
+    pub fn len(&self) -> usize {
+        self.seq.len()
+    }
+
+    /// Returns `true` if the sequence holds no positions.
+    pub fn is_empty(&self) -> bool {
+        self.seq.is_empty()
+    }
+
+    /// Returns the symbol at `idx`, or `None` if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<MaskedNuc> {
+        let nuc = self.seq.get(idx)?;
+        if self.n_runs.contains(idx) {
+            Some(MaskedNuc::N)
+        } else {
+            Some(MaskedNuc::Nuc(nuc))
+        }
+    }
+
+    /// Returns `true` if `idx` is inside an `N` run.
+    pub fn is_n(&self, idx: usize) -> bool {
+        self.n_runs.contains(idx)
+    }
+
+    /// Returns the runs of `N` as sorted, disjoint, half-open ranges.
+    pub fn n_runs(&self) -> &[Range<usize>] {
+        self.n_runs.as_slice()
+    }
+
+    /// Returns `true` if `idx` is soft-masked.
+    pub fn is_masked(&self, idx: usize) -> bool {
+        self.soft_mask.contains(idx)
+    }
+
+    /// Returns the soft-masked regions as sorted, disjoint, half-open
+    /// ranges.
+    pub fn masked_intervals(&self) -> &[Range<usize>] {
+        self.soft_mask.as_slice()
+    }
+
+    /// Returns the underlying packed nucleotides. Positions inside `N` runs
+    /// read as A.
+    pub fn as_packed(&self) -> &PackedDna {
+        &self.seq
+    }
+
+    /// Appends a symbol to the end of the sequence.
+    pub fn push(&mut self, symbol: MaskedNuc) {
+        match symbol {
+            MaskedNuc::Nuc(nuc) => self.seq.push(nuc),
+            MaskedNuc::N => {
+                self.n_runs.push_pos(self.seq.len());
+                self.seq.push(Nuc::A);
+            }
+        }
+    }
+
+    /// Appends a symbol, recording it as soft-masked if `masked` is set.
+    pub(crate) fn push_with_mask(&mut self, symbol: MaskedNuc, masked: bool) {
+        if masked {
+            self.soft_mask.push_pos(self.len());
+        }
+        self.push(symbol);
+    }
+
+    /// Returns an iterator over the symbols of the sequence.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = MaskedNuc> + ExactSizeIterator + '_ {
+        MaskedIter {
+            nucs: self.seq.iter(),
+            n_runs: self.n_runs(),
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    /// Counts the nucleotides of the sequence; `N` positions are reported
+    /// through [`NucCounts::n`] rather than as any nucleotide.
+    pub fn counts(&self) -> NucCounts {
+        let mut counts = self.seq.counts();
+        counts.mask_n(self.n_runs.covered(0..self.len()));
+        counts
+    }
+
+    /// Copies out the positions in `range`, keeping the `N` runs and
+    /// soft-masked regions that fall inside it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is after its end.
+    pub fn subseq<R: RangeBounds<usize>>(&self, range: R) -> MaskedPackedDna {
+        let range = resolve_range(range, self.len());
+        MaskedPackedDna {
+            seq: self.seq.slice(range.clone()).to_owned(),
+            n_runs: self.n_runs.restrict(range.clone()),
+            soft_mask: self.soft_mask.restrict(range),
+        }
+    }
+
+    /// Returns the reverse complement of the sequence, with the `N` runs and
+    /// soft-masked regions moved to their positions on the opposite strand.
+    pub fn reverse_complement(&self) -> MaskedPackedDna {
+        let mut seq = self.seq.reverse_complement();
+        let n_runs = self.n_runs.reverse(self.len());
+        // complementing turned the A stored under each `N` run into T
+        for run in n_runs.as_slice() {
+            seq.clear_range(run.clone());
+        }
+        MaskedPackedDna {
+            seq,
+            n_runs,
+            soft_mask: self.soft_mask.reverse(self.len()),
+        }
+    }
+
+    /// Writes the sequence as ASCII, passing it to `write` in chunks of up
+    /// to one word and stopping at the first error. Soft-masked positions
+    /// are written in lowercase if `soft_mask` is set.
+    pub(crate) fn for_each_ascii<E, F>(&self, soft_mask: bool, mut write: F) -> Result<(), E>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>,
+    {
+        // split the sequence wherever an `N` run or a soft-masked region
+        // starts or ends, so every stretch is written the same way
+        let mut bounds: Vec<usize> = self
+            .n_runs()
+            .iter()
+            .chain(self.masked_intervals())
+            .flat_map(|range| vec![range.start, range.end])
+            .chain(vec![0, self.len()])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+        for stretch in bounds.windows(2) {
+            let (start, end) = (stretch[0], stretch[1]);
+            let lower = soft_mask && self.is_masked(start);
+            if self.is_n(start) {
+                let n = if lower { [b'n'; 32] } else { [b'N'; 32] };
+                let mut remaining = end - start;
+                while remaining > 0 {
+                    let count = remaining.min(n.len());
+                    write(&n[..count])?;
+                    remaining -= count;
+                }
+            } else if lower {
+                self.seq.slice(start..end).for_each_ascii(|chunk| {
+                    let mut buf = [0u8; 32];
+                    let buf = &mut buf[..chunk.len()];
+                    buf.copy_from_slice(chunk);
+                    buf.make_ascii_lowercase();
+                    write(buf)
+                })?;
+            } else {
+                self.seq.slice(start..end).for_each_ascii(&mut write)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The iterator behind [`MaskedPackedDna::iter`]. It walks the sorted `N`
+/// runs alongside the nucleotides from either end, dropping runs once they
+/// are passed, rather than searching them for every position.
+#[derive(Debug, Clone)]
+struct MaskedIter<'a> {
+    nucs: Iter<'a>,
+    // the `N` runs that may still hold a position between `front` and `back`
+    n_runs: &'a [Range<usize>],
+    front: usize,
+    back: usize,
+}
+
+impl Iterator for MaskedIter<'_> {
+    type Item = MaskedNuc;
+
+    fn next(&mut self) -> Option<MaskedNuc> {
+        let nuc = self.nucs.next()?;
+        let idx = self.front;
+        self.front += 1;
+        while let Some((run, rest)) = self.n_runs.split_first() {
+            if idx < run.end {
+                break;
+            }
+            self.n_runs = rest;
+        }
+        match self.n_runs.first() {
+            Some(run) if run.start <= idx => Some(MaskedNuc::N),
+            _ => Some(MaskedNuc::Nuc(nuc)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nucs.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for MaskedIter<'_> {
+    fn next_back(&mut self) -> Option<MaskedNuc> {
+        let nuc = self.nucs.next_back()?;
+        self.back -= 1;
+        let idx = self.back;
+        while let Some((run, rest)) = self.n_runs.split_last() {
+            if run.start <= idx {
+                break;
+            }
+            self.n_runs = rest;
+        }
+        match self.n_runs.last() {
+            Some(run) if idx < run.end => Some(MaskedNuc::N),
+            _ => Some(MaskedNuc::Nuc(nuc)),
+        }
+    }
+}
+
+impl ExactSizeIterator for MaskedIter<'_> {}
+
+impl From<PackedDna> for MaskedPackedDna {
+    fn from(seq: PackedDna) -> MaskedPackedDna {
+        MaskedPackedDna {
+            seq,
+            n_runs: Intervals::new(),
+            soft_mask: Intervals::new(),
+        }
+    }
+}
+
+impl FromIterator<MaskedNuc> for MaskedPackedDna {
+    fn from_iter<I: IntoIterator<Item = MaskedNuc>>(iter: I) -> Self {
+        let mut seq = MaskedPackedDna::new();
+        for symbol in iter {
+            seq.push(symbol);
+        }
+        seq
+    }
+}
+
+/// Fails with [`AmbiguousNucError`] at the first `N`. The soft mask is
+/// dropped.
+impl TryFrom<&MaskedPackedDna> for PackedDna {
+    type Error = AmbiguousNucError;
+
+    fn try_from(seq: &MaskedPackedDna) -> Result<Self, Self::Error> {
+        match seq.n_runs().first() {
+            Some(run) => Err(AmbiguousNucError {
+                position: run.start,
+                symbol: IupacNuc::N,
+            }),
+            None => Ok(seq.seq.clone()),
+        }
+    }
+}
+
+impl From<&MaskedPackedDna> for PackedIupacDna {
+    fn from(seq: &MaskedPackedDna) -> PackedIupacDna {
+        seq.iter().map(IupacNuc::from).collect()
+    }
+}
+
+impl ParseOptions {
+    /// Parses `s` into a [`MaskedPackedDna`] using these options. Parsing is
+    /// case insensitive and accepts A, C, G, T and N.
+    pub fn parse_masked(&self, s: &str) -> Result<MaskedPackedDna, ParseDnaError> {
+        self.parse_masked_with(s, false)
+    }
+
+    /// Parses `s` like [`ParseOptions::parse_masked`], and when `soft_mask`
+    /// is set also records lowercase positions as soft-masked, the way
+    /// RepeatMasker marks repeats in reference FASTA.
+    pub fn parse_masked_with(
+        &self,
+        s: &str,
+        soft_mask: bool,
+    ) -> Result<MaskedPackedDna, ParseDnaError> {
+        let mut seq = MaskedPackedDna::new();
+        seq.seq.reserve(s.len());
+        self.scan(s, |c| match MaskedNuc::try_from(c) {
+            Ok(symbol) => {
+                seq.push_with_mask(symbol, soft_mask && c.is_ascii_lowercase());
+                Ok(true)
+            }
+            Err(_) => Ok(false),
+        })?;
+        Ok(seq)
+    }
+}
+
+/// Parses a case insensitive string of A, C, G, T and N, reporting errors
+/// the same way [`PackedDna::from_str`] does.
+impl FromStr for MaskedPackedDna {
+    type Err = ParseDnaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ParseOptions::new().parse_masked(s)
+    }
+}
+
+impl Display for MaskedPackedDna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.for_each_ascii(true, |chunk| {
+            // the chunks only ever hold ASCII letters
+            f.write_str(str::from_utf8(chunk).expect("ASCII nucleotides"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "NNACGTNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNGATTACANGGNCCN";
+
+    // Test to check that parsing keeps every `N` and that lookups,
+    // iteration and formatting agree with the text
+    #[test]
+    fn test_masked_parse() {
+        let seq = MaskedPackedDna::from_str(TEXT).unwrap();
+        assert_eq!(seq.len(), TEXT.len());
+        assert_eq!(seq.n_runs(), &[0..2, 6..43, 50..51, 53..54, 56..57]);
+        assert_eq!(seq.to_string(), TEXT);
+        for (idx, c) in TEXT.chars().enumerate() {
+            assert_eq!(seq.get(idx).map(char::from), Some(c));
+            assert_eq!(seq.is_n(idx), c == 'N');
+        }
+        assert_eq!(seq.get(TEXT.len()), None);
+        assert_eq!(seq.iter().map(char::from).collect::<String>(), TEXT);
+        assert_eq!(seq.iter().rev().count(), TEXT.len());
+        assert_eq!(
+            seq.iter().rev().map(char::from).collect::<String>(),
+            TEXT.chars().rev().collect::<String>()
+        );
+        // alternate ends so both cursors walk the same runs
+        let mut iter = seq.iter();
+        let (mut front, mut back) = (String::new(), String::new());
+        while let Some(symbol) = iter.next() {
+            front.push(char::from(symbol));
+            back.extend(iter.next_back().map(char::from));
+        }
+        back = back.chars().rev().collect();
+        assert_eq!(front + &back, TEXT);
+        assert_eq!(
+            MaskedPackedDna::from_str("acgtnN").unwrap().to_string(),
+            "ACGTNN"
+        );
+        assert_eq!(
+            MaskedPackedDna::from_str("ACRT"),
+            Err(ParseDnaError::InvalidSymbol {
+                offset: 2,
+                symbol: 'R'
+            })
+        );
+    }
+
+    // Test to check that counts skip the `N` runs, including on
+    // sub-sequences that cut through them
+    #[test]
+    fn test_masked_counts_subseq() {
+        let seq = MaskedPackedDna::from_str(TEXT).unwrap();
+        let counts = seq.counts();
+        assert_eq!(counts.n(), 42);
+        assert_eq!(counts[Nuc::A], 4);
+        assert_eq!(counts[Nuc::G], 4);
+        assert_eq!(counts.total(), TEXT.len());
+        for (start, end) in [(0, 1), (1, 10), (5, 45), (40, 57), (51, 53)] {
+            let sub = seq.subseq(start..end);
+            assert_eq!(sub, MaskedPackedDna::from_str(&TEXT[start..end]).unwrap());
+            let expected = TEXT[start..end].chars().filter(|&c| c == 'N').count();
+            assert_eq!(sub.counts().n(), expected);
+        }
+    }
+
+    // Test to check the reverse complement and the conversions to the
+    // other sequence types
+    #[test]
+    fn test_masked_reverse_complement_and_conversions() {
+        let seq = MaskedPackedDna::from_str("AANNCGTN").unwrap();
+        let revcomp = seq.reverse_complement();
+        assert_eq!(revcomp, MaskedPackedDna::from_str("NACGNNTT").unwrap());
+        assert_eq!(revcomp.reverse_complement(), seq);
+        assert_eq!(
+            PackedIupacDna::from(&seq),
+            PackedIupacDna::from_str("AANNCGTN").unwrap()
+        );
+        assert_eq!(
+            PackedDna::try_from(&seq),
+            Err(AmbiguousNucError {
+                position: 2,
+                symbol: IupacNuc::N
+            })
+        );
+        let dna = PackedDna::from_str("ACGT").unwrap();
+        let plain = MaskedPackedDna::from(dna.clone());
+        assert!(plain.n_runs().is_empty());
+        assert_eq!(PackedDna::try_from(&plain).unwrap(), dna);
+    }
+
+    // Test to check that the soft mask is captured from lowercase, carried
+    // through sub-sequences and the reverse complement, and written back out
+    #[test]
+    fn test_soft_mask() {
+        let text = "ACGTacgtnnNNacGTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTTttttgNa";
+        let seq = ParseOptions::new().parse_masked_with(text, true).unwrap();
+        assert_eq!(seq.masked_intervals(), &[4..10, 12..14, 54..59, 60..61]);
+        assert_eq!(seq.n_runs(), &[8..12, 59..60]);
+        assert_eq!(seq.to_string(), text);
+        for (idx, c) in text.chars().enumerate() {
+            assert_eq!(seq.is_masked(idx), c.is_ascii_lowercase());
+        }
+        for (start, end) in [(0, 5), (5, 13), (13, 58), (50, 61)] {
+            assert_eq!(seq.subseq(start..end).to_string(), &text[start..end]);
+        }
+        let revcomp = seq.reverse_complement();
+        assert_eq!(
+            revcomp.to_string(),
+            "tNcaaaaAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACgtNNnnacgtACGT"
+        );
+        assert_eq!(revcomp.reverse_complement(), seq);
+
+        // the mask is only kept when asked for
+        let unmasked = MaskedPackedDna::from_str(text).unwrap();
+        assert!(unmasked.masked_intervals().is_empty());
+        assert_eq!(unmasked.to_string(), text.to_ascii_uppercase());
+    }
+}
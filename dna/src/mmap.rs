@@ -0,0 +1,206 @@
+//! [`MmapPackedDna`], a read-only sequence mapped straight from a file.
+
+use std::{convert::TryFrom, fs::File, io, ops::RangeBounds, path::Path};
+
+use memmap2::Mmap;
+
+#[cfg(doc)]
+use crate::PackedDna;
+use crate::{
+    binary::{check_padding, parse_header, HEADER_LEN, LOW_BITS_FIRST},
+    packed::words_for,
+    Iter, Nuc, NucCounts, PackedDnaSlice, ReadDnaError,
+};
+
+/// A read-only sequence memory-mapped from a file written by
+/// [`PackedDna::write_to`](crate::PackedDna::write_to).
+///
+/// The packed words are used in place, so opening a file costs the same
+/// whatever its size, and every process mapping the same file shares one
+/// copy of it in the page cache. Reads go through
+/// [`MmapPackedDna::as_slice`], which offers the read-only API of
+/// [`PackedDnaSlice`]; the most common methods are repeated here.
+///
+/// Since the words are used as they are, only files in the low bits first
+/// bit order can be mapped, and only on little endian targets. The checksum
+/// is not checked on opening, as that would read the whole file; call
+/// [`MmapPackedDna::verify`] to check it.
+///
+/// ```no_run
+/// use dna::MmapPackedDna;
+///
+/// let genome = MmapPackedDna::open("genome.pdna").unwrap();
+/// let window = genome.slice(1_000_000..1_000_100);
+/// println!("{} {:?}", window, window.counts().gc_fraction());
+/// ```
+#[derive(Debug)]
+pub struct MmapPackedDna {
+    map: Mmap,
+    len: usize,
+}
+
+impl MmapPackedDna {
+    /// Maps the file at `path`, checking its header and size.
+    ///
+    /// The file must not be modified while it is mapped; the operating
+    /// system gives no protection against another process truncating or
+    /// rewriting it.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MmapPackedDna, ReadDnaError> {
+        let file = File::open(path).map_err(ReadDnaError::Io)?;
+        // SAFETY: the mapping is only ever read, and the caller is told not
+        // to modify the file while it is mapped
+        let map = unsafe { Mmap::map(&file) }.map_err(ReadDnaError::Io)?;
+
+        let header =
+            <&[u8; HEADER_LEN]>::try_from(map.get(..HEADER_LEN).ok_or(ReadDnaError::Truncated)?)
+                .expect("header length");
+        let (len, bit_order) = parse_header(header)?;
+        if bit_order != LOW_BITS_FIRST || cfg!(target_endian = "big") {
+            return Err(ReadDnaError::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "only low bits first packed DNA can be mapped, on little endian targets",
+            )));
+        }
+        let needed = words_for(len)
+            .checked_mul(8)
+            .and_then(|bytes| bytes.checked_add(HEADER_LEN + 4))
+            .ok_or(ReadDnaError::TooLong(len as u64))?;
+        if map.len() < needed {
+            return Err(ReadDnaError::Truncated);
+        }
+        let seq = MmapPackedDna { map, len };
+        check_padding(seq.words(), len)?;
+        Ok(seq)
+    }
+
+    /// Returns the packed words, read in place from the mapping.
+    fn words(&self) -> &[u64] {
+        let bytes = &self.map[HEADER_LEN..HEADER_LEN + words_for(self.len) * 8];
+        // SAFETY: mappings start on a page boundary and the words start 16
+        // bytes in, so they are aligned for `u64`; every bit pattern is a
+        // valid `u64`, and `open` checked the file holds all of them in
+        // little endian order on a little endian target
+        debug_assert_eq!(bytes.as_ptr().align_offset(8), 0);
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const u64, bytes.len() / 8) }
+    }
+
+    /// Reads the whole file and checks it against its checksum.
+    pub fn verify(&self) -> Result<(), ReadDnaError> {
+        let end = HEADER_LEN + words_for(self.len) * 8;
+        let mut stored = [0u8; 4];
+        stored.copy_from_slice(&self.map[end..end + 4]);
+        let stored = u32::from_le_bytes(stored);
+        let computed = crc32fast::hash(&self.map[..end]);
+        if stored == computed {
+            Ok(())
+        } else {
+            Err(ReadDnaError::ChecksumMismatch { stored, computed })
+        }
+    }
+
+    /// Returns a view of the whole sequence.
+    pub fn as_slice(&self) -> PackedDnaSlice<'_> {
+        PackedDnaSlice::new(self.words(), 0, self.len)
+    }
+
+    /// Returns the number of nucleotides.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the sequence holds no nucleotides.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the nucleotide at `idx`, or `None` if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<Nuc> {
+        self.as_slice().get(idx)
+    }
+
+    /// Returns a view of the nucleotides in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is after its end.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> PackedDnaSlice<'_> {
+        self.as_slice().slice(range)
+    }
+
+    /// Returns an iterator over the nucleotides.
+    pub fn iter(&self) -> Iter<'_> {
+        self.as_slice().iter()
+    }
+
+    /// Counts the occurrences of each nucleotide.
+    pub fn counts(&self) -> NucCounts {
+        self.as_slice().counts()
+    }
+
+    /// Returns the start of the first occurrence of `pattern`, see
+    /// [`PackedDna::find`](crate::PackedDna::find).
+    pub fn find(&self, pattern: PackedDnaSlice<'_>) -> Option<usize> {
+        self.as_slice().find(pattern)
+    }
+
+    /// Returns the start of the last occurrence of `pattern`.
+    pub fn rfind(&self, pattern: PackedDnaSlice<'_>) -> Option<usize> {
+        self.as_slice().rfind(pattern)
+    }
+
+    /// Returns an iterator over the starts of every occurrence of `pattern`.
+    pub fn find_iter<'a>(
+        &'a self,
+        pattern: PackedDnaSlice<'a>,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.as_slice().find_iter(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackedDna;
+    use std::str::FromStr;
+
+    // Test to check that a written sequence maps back unchanged and that
+    // truncated or corrupted files are caught
+    #[test]
+    fn test_map_file() {
+        let dna = PackedDna::from_str(&"GATTACACCA".repeat(50)).unwrap();
+        let mut bytes = Vec::new();
+        dna.write_to(&mut bytes).unwrap();
+        let path = std::env::temp_dir().join(format!("dna-mmap-{}.pdna", std::process::id()));
+
+        std::fs::write(&path, &bytes).unwrap();
+        let mapped = MmapPackedDna::open(&path).unwrap();
+        mapped.verify().unwrap();
+        assert_eq!(mapped.len(), 500);
+        assert_eq!(mapped.as_slice().to_owned(), dna);
+        assert_eq!(mapped.get(3), Some(Nuc::T));
+        assert_eq!(mapped.slice(7..12).to_string(), "CCAGA");
+        assert_eq!(mapped.counts(), dna.counts());
+        assert!(mapped.iter().eq(dna.iter()));
+        let motif = PackedDna::from_str("ACACC").unwrap();
+        assert_eq!(mapped.find(motif.as_slice()), Some(4));
+        assert_eq!(mapped.rfind(motif.as_slice()), Some(494));
+        assert_eq!(mapped.find_iter(motif.as_slice()).count(), 50);
+        drop(mapped);
+
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            MmapPackedDna::open(&path),
+            Err(ReadDnaError::Truncated)
+        ));
+        let mut corrupt = bytes.clone();
+        corrupt[30] ^= 0xff;
+        std::fs::write(&path, &corrupt).unwrap();
+        let mapped = MmapPackedDna::open(&path).unwrap();
+        assert!(matches!(
+            mapped.verify(),
+            Err(ReadDnaError::ChecksumMismatch { .. })
+        ));
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+//! Six frame open reading frame (ORF) search.
+
+use crate::{
+    codon::{codons, Codon},
+    GeneticCode, Nuc, PackedDna, PackedDnaSlice, Strand,
+};
+
+/// An open reading frame: a run of codons from a start codon up to and
+/// including the next in-frame stop codon.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Orf {
+    /// The strand the ORF is read from.
+    pub strand: Strand,
+    /// The reading frame (0, 1 or 2), counted from the 5' end of `strand`.
+    pub frame: usize,
+    /// Start of the ORF on the forward strand, inclusive.
+    pub start: usize,
+    /// End of the ORF on the forward strand, exclusive. The stop codon is
+    /// included, so `end - start` is always a multiple of three.
+    pub end: usize,
+    /// The translated protein, without the stop codon. The start codon is
+    /// always translated as `M`.
+    pub protein: String,
+}
+
+/// Options controlling which ORFs [`PackedDna::orfs`] reports.
+///
+/// By default every codon the genetic code lists as a start can open an
+/// ORF, and only the longest ORF ending at each stop codon is reported.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{GeneticCode, OrfOptions, PackedDna, Strand};
+///
+/// let dna = PackedDna::from_str("CCATGAAATTGCCCTAAGG").unwrap();
+/// let code = GeneticCode::standard();
+/// let orfs = dna.orfs(2, &code);
+/// assert_eq!(orfs.len(), 1);
+/// assert_eq!((orfs[0].strand, orfs[0].start, orfs[0].end), (Strand::Forward, 2, 17));
+/// assert_eq!(orfs[0].protein, "MKLP");
+///
+/// let nested = OrfOptions::new().nested(true).orfs(dna.as_slice(), 2, &code);
+/// let proteins: Vec<_> = nested.iter().map(|orf| orf.protein.as_str()).collect();
+/// assert_eq!(proteins, ["MKLP", "MP"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrfOptions {
+    nested: bool,
+    atg_only: bool,
+}
+
+impl OrfOptions {
+    /// Creates the default ORF options.
+    pub fn new() -> OrfOptions {
+        OrfOptions::default()
+    }
+
+    /// When enabled, every start codon opens its own ORF, so ORFs that lie
+    /// inside a longer one sharing its stop codon are reported as well.
+    pub fn nested(mut self, yes: bool) -> OrfOptions {
+        self.nested = yes;
+        self
+    }
+
+    /// When enabled, only ATG opens an ORF, ignoring the alternative start
+    /// codons of the genetic code.
+    pub fn atg_only(mut self, yes: bool) -> OrfOptions {
+        self.atg_only = yes;
+        self
+    }
+
+    /// Finds the ORFs of `seq` in all six reading frames whose protein is
+    /// at least `min_len` amino acids long. ORFs that run off the end of the
+    /// sequence without reaching a stop codon are not reported.
+    ///
+    /// The forward strand is searched first, then the reverse strand, each
+    /// frame by frame, with the ORFs of a frame in reading order.
+    pub fn orfs(&self, seq: PackedDnaSlice<'_>, min_len: usize, code: &GeneticCode) -> Vec<Orf> {
+        let mut orfs = Vec::new();
+        let reverse = seq.reverse_complement();
+        for (strand, strand_seq) in [
+            (Strand::Forward, seq),
+            (Strand::Reverse, reverse.as_slice()),
+        ] {
+            for frame in 0..3 {
+                self.frame_orfs(strand_seq, strand, frame, min_len, code, &mut orfs);
+            }
+        }
+        orfs
+    }
+
+    /// Appends the ORFs of one frame of `seq`, which is already read in the
+    /// direction of `strand`.
+    fn frame_orfs(
+        &self,
+        seq: PackedDnaSlice<'_>,
+        strand: Strand,
+        frame: usize,
+        min_len: usize,
+        code: &GeneticCode,
+        orfs: &mut Vec<Orf>,
+    ) {
+        let atg = Codon::new(Nuc::A, Nuc::T, Nuc::G);
+        let mut amino_acids = String::new();
+        // codon indices of the start codons seen since the last stop
+        let mut starts = Vec::new();
+        for (idx, codon) in codons(seq, frame).enumerate() {
+            let is_start = if self.atg_only {
+                codon == atg
+            } else {
+                code.is_start(codon)
+            };
+            if is_start && (self.nested || starts.is_empty()) {
+                starts.push(idx);
+            }
+            let amino_acid = code.amino_acid(codon);
+            amino_acids.push(amino_acid);
+            if amino_acid != '*' {
+                continue;
+            }
+            for start in starts.drain(..) {
+                if idx - start < min_len {
+                    continue;
+                }
+                // offsets along `seq`, converted below to the forward strand
+                let (from, to) = (frame + 3 * start, frame + 3 * (idx + 1));
+                let (start_pos, end_pos) = match strand {
+                    Strand::Forward => (from, to),
+                    Strand::Reverse => (seq.len() - to, seq.len() - from),
+                };
+                let mut protein = String::with_capacity(idx - start);
+                protein.push('M');
+                protein.push_str(&amino_acids[start + 1..idx]);
+                orfs.push(Orf {
+                    strand,
+                    frame,
+                    start: start_pos,
+                    end: end_pos,
+                    protein,
+                });
+            }
+        }
+    }
+}
+
+impl PackedDna {
+    /// Finds the ORFs in all six reading frames whose protein is at least
+    /// `min_len` amino acids long, with the default [`OrfOptions`].
+    pub fn orfs(&self, min_len: usize, code: &GeneticCode) -> Vec<Orf> {
+        OrfOptions::new().orfs(self.as_slice(), min_len, code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // Test to check ORFs on both strands, their coordinates and that each
+    // protein matches translating the reported range
+    #[test]
+    fn test_orfs_six_frames() {
+        // a forward ORF in frame 1 and, read backwards near the end,
+        // ATG AAA CCC TGA on the reverse strand
+        let text = "GATGCCCGGGTAGCTTCATCAGGGTTTCATTTT";
+        let dna = PackedDna::from_str(text).unwrap();
+        let code = GeneticCode::standard();
+        let orfs = dna.orfs(2, &code);
+        assert_eq!(
+            orfs,
+            [
+                Orf {
+                    strand: Strand::Forward,
+                    frame: 1,
+                    start: 1,
+                    end: 13,
+                    protein: "MPG".to_string(),
+                },
+                Orf {
+                    strand: Strand::Reverse,
+                    frame: 0,
+                    start: 18,
+                    end: 30,
+                    protein: "MKP".to_string(),
+                },
+            ]
+        );
+        for orf in &orfs {
+            let range = dna.slice(orf.start..orf.end).to_owned();
+            let seq = match orf.strand {
+                Strand::Forward => range,
+                Strand::Reverse => range.reverse_complement(),
+            };
+            let protein = seq.translate(0, &code);
+            assert_eq!(&protein[1..protein.len() - 1], &orf.protein[1..]);
+            assert!(protein.ends_with('*'));
+        }
+        assert!(dna.orfs(5, &code).is_empty());
+    }
+
+    // Test to check nested ORFs and the ATG only option
+    #[test]
+    fn test_orf_options() {
+        let dna = PackedDna::from_str("TTGAAAATGCCCTAA").unwrap();
+        let code = GeneticCode::standard();
+        let forward = |orfs: Vec<Orf>| -> Vec<String> {
+            orfs.into_iter()
+                .filter(|orf| orf.strand == Strand::Forward)
+                .map(|orf| orf.protein)
+                .collect()
+        };
+        assert_eq!(forward(dna.orfs(1, &code)), ["MKMP"]);
+        let atg = OrfOptions::new().atg_only(true);
+        assert_eq!(forward(atg.orfs(dna.as_slice(), 1, &code)), ["MP"]);
+        let nested = OrfOptions::new().nested(true);
+        assert_eq!(
+            forward(nested.orfs(dna.as_slice(), 1, &code)),
+            ["MKMP", "MP"]
+        );
+        assert_eq!(forward(nested.orfs(dna.as_slice(), 3, &code)), ["MKMP"]);
+        // no stop codon, no ORF
+        let open = PackedDna::from_str("ATGAAAAAA").unwrap();
+        assert!(forward(open.orfs(0, &code)).is_empty());
+    }
+}
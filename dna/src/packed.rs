@@ -0,0 +1,1057 @@
+//! The [`PackedDna`] sequence type, which stores DNA using two bits per
+//! nucleotide, together with the builder and parser that create it.
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    iter::FromIterator,
+    ops::{Range, RangeBounds},
+    str::FromStr,
+};
+
+use crate::{Iter, Nuc, NucCounts, PackedDnaSlice};
+
+// number of nucleotides packed into each `u64` word
+pub(crate) const NUCS_PER_WORD: usize = 32;
+
+/// Returns the number of words needed to hold `len` nucleotides.
+pub(crate) fn words_for(len: usize) -> usize {
+    len / NUCS_PER_WORD + usize::from(len & (NUCS_PER_WORD - 1) != 0)
+}
+
+/// Returns a mask covering the two bit slots below `slot` in a word.
+fn mask_below(slot: usize) -> u64 {
+    (1u64 << (slot * 2)) - 1
+}
+
+/// Returns the index of the word holding nucleotide `pos` and the bit
+/// offset of its slot within that word.
+pub(crate) fn locate(pos: usize) -> (usize, usize) {
+    (pos / NUCS_PER_WORD, (pos % NUCS_PER_WORD) * 2)
+}
+
+/// Returns the 32 nucleotides of `data` starting at nucleotide `start` as a
+/// single word, first nucleotide in the low bits. Slots past the end of
+/// `data` read as zero.
+pub(crate) fn word_at(data: &[u64], start: usize) -> u64 {
+    let (idx, shift) = locate(start);
+    let low = data.get(idx).map_or(0, |word| word >> shift);
+    if shift == 0 {
+        low
+    } else {
+        low | data.get(idx + 1).map_or(0, |word| word << (64 - shift))
+    }
+}
+
+/// Reverses the order of the 32 two bit slots in a word.
+pub(crate) fn reverse_slots(word: u64) -> u64 {
+    // swap neighbouring slots, then neighbouring pairs of slots; the byte
+    // swap reverses the order of the resulting four-slot bytes
+    let word = (word >> 2) & 0x3333_3333_3333_3333 | (word & 0x3333_3333_3333_3333) << 2;
+    let word = (word >> 4) & 0x0f0f_0f0f_0f0f_0f0f | (word & 0x0f0f_0f0f_0f0f_0f0f) << 4;
+    word.swap_bytes()
+}
+
+/// Returns a word with every two bit slot set to `nuc`.
+pub(crate) fn fill_word(nuc: Nuc) -> u64 {
+    u64::from(nuc.bits()) * 0x5555_5555_5555_5555
+}
+
+/// A DNA struct - stores the sequence of nucleotide in a
+/// memory efficient format
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PackedDna {
+    // sequence of nucleotide stored in memory efficient format: 32
+    // nucleotides per word, the first one in the low bits. Bits past the
+    // end of the sequence in the last word are always zero, so words can be
+    // compared, hashed and counted directly.
+    data: Vec<u64>,
+    // number of nucleotides stored; a `usize` so that sequences above
+    // `u32::MAX` bases (large plant genomes, pooled reads) are supported
+    data_len: usize,
+}
+
+impl PackedDna {
+    /// This function creates a new instance of PackedDna from already
+    /// packed words and returns the created struct instance to the caller
+    pub(crate) fn from_raw(data: Vec<u64>, data_len: usize) -> PackedDna {
+        debug_assert_eq!(data.len(), words_for(data_len));
+        PackedDna { data, data_len }
+    }
+
+    /// Creates an empty sequence.
+    pub fn new() -> PackedDna {
+        PackedDna::default()
+    }
+
+    /// Creates an empty sequence with room for at least `capacity`
+    /// nucleotides before reallocating.
+    pub fn with_capacity(capacity: usize) -> PackedDna {
+        PackedDna::from_raw(Vec::with_capacity(words_for(capacity)), 0)
+    }
+
+    /// Returns the number of nucleotides the sequence can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.data.capacity().saturating_mul(NUCS_PER_WORD)
+    }
+
+    /// Reserves room for at least `additional` more nucleotides.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new length overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        let new_len = self
+            .data_len
+            .checked_add(additional)
+            .expect("PackedDna capacity overflow");
+        self.data.reserve(words_for(new_len) - self.data.len());
+    }
+
+    /// Shrinks the backing storage as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.data.shrink_to_fit();
+    }
+
+    /// Returns the number of nucleotides stored in the sequence.
+    pub fn len(&self) -> usize {
+        self.data_len
+    }
+
+    /// Returns `true` if the sequence holds no nucleotides.
+    pub fn is_empty(&self) -> bool {
+        self.data_len == 0
+    }
+
+    /// Returns the packed words backing the sequence.
+    pub(crate) fn words(&self) -> &[u64] {
+        &self.data
+    }
+
+    /// Returns an iterator over the nucleotides of the sequence.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(&self.data, 0, self.data_len)
+    }
+
+    /// Counts the occurrences of each nucleotide in the sequence.
+    pub fn counts(&self) -> NucCounts {
+        NucCounts::from_words(self.data.iter().copied(), self.data_len)
+    }
+
+    /// Returns a borrowed view of the whole sequence.
+    pub fn as_slice(&self) -> PackedDnaSlice<'_> {
+        PackedDnaSlice::new(&self.data, 0, self.data_len)
+    }
+
+    /// Returns a borrowed view of the nucleotides in `range` without
+    /// copying them. The range may start at any offset.
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use dna::PackedDna;
+    ///
+    /// let dna = PackedDna::from_str("ACGTTGCA").unwrap();
+    /// assert_eq!(dna.slice(3..6).to_string(), "TTG");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is after its end.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> PackedDnaSlice<'_> {
+        self.as_slice().slice(range)
+    }
+
+    /// Returns the nucleotide stored at `idx`, or `None` if `idx` is out of
+    /// bounds.
+    ///
+    /// Nucleotides are packed 32 to a word starting from the low bits, so
+    /// the nucleotide at `idx` lives in word `idx / 32` at bit `2 * (idx % 32)`.
+    pub fn get(&self, idx: usize) -> Option<Nuc> {
+        if idx >= self.len() {
+            return None;
+        }
+        // SAFETY: `idx` was bounds checked against the length above.
+        Some(unsafe { self.get_unchecked(idx) })
+    }
+
+    /// Returns the nucleotide stored at `idx` without bounds checking.
+    ///
+    /// This is intended for hot loops where the index is already known to
+    /// be valid; prefer [`PackedDna::get`] everywhere else.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than [`PackedDna::len`].
+    pub unsafe fn get_unchecked(&self, idx: usize) -> Nuc {
+        let (word, shift) = locate(idx);
+        Nuc::from_bits((*self.data.get_unchecked(word) >> shift) as u8)
+    }
+
+    /// Appends a nucleotide to the end of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length overflows `usize`.
+    pub fn push(&mut self, nuc: Nuc) {
+        let slot = self.data_len % NUCS_PER_WORD;
+        self.data_len = self
+            .data_len
+            .checked_add(1)
+            .expect("PackedDna capacity overflow");
+        if slot == 0 {
+            self.data.push(0u64);
+        }
+        let last = self.data.len() - 1;
+        self.data[last] |= u64::from(nuc.bits()) << (slot * 2);
+    }
+
+    /// Removes the last nucleotide and returns it, or `None` if the
+    /// sequence is empty.
+    pub fn pop(&mut self) -> Option<Nuc> {
+        let nuc = self.get(self.data_len.checked_sub(1)?)?;
+        self.truncate(self.data_len - 1);
+        Some(nuc)
+    }
+
+    /// Replaces the nucleotide at `idx` with `nuc`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn set(&mut self, idx: usize, nuc: Nuc) {
+        assert!(
+            idx < self.data_len,
+            "index {} out of bounds for PackedDna of length {}",
+            idx,
+            self.data_len
+        );
+        let (word, shift) = locate(idx);
+        let word = &mut self.data[word];
+        *word = (*word & !(3u64 << shift)) | u64::from(nuc.bits()) << shift;
+    }
+
+    /// Sets every nucleotide in `range` to A, clearing whole words at once
+    /// and masking only the partial words at either end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub(crate) fn clear_range(&mut self, range: Range<usize>) {
+        assert!(
+            range.end <= self.data_len,
+            "range end {} out of bounds for PackedDna of length {}",
+            range.end,
+            self.data_len
+        );
+        if range.start >= range.end {
+            return;
+        }
+        let (first, start_shift) = locate(range.start);
+        let (last, end_shift) = locate(range.end);
+        // the slots of the first word before the range and of the last word
+        // from its end onwards are kept
+        let keep_low = mask_below(start_shift / 2);
+        if first == last {
+            self.data[first] &= keep_low | !mask_below(end_shift / 2);
+            return;
+        }
+        self.data[first] &= keep_low;
+        for word in &mut self.data[first + 1..last] {
+            *word = 0;
+        }
+        if end_shift != 0 {
+            self.data[last] &= !mask_below(end_shift / 2);
+        }
+    }
+
+    /// Shortens the sequence to its first `len` nucleotides. Has no effect
+    /// if `len` is not less than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.data_len {
+            return;
+        }
+        self.data.truncate(words_for(len));
+        self.data_len = len;
+        // keep the bits past the end of the sequence zeroed
+        self.clear_padding();
+    }
+
+    /// Inserts `nuc` at `idx`, shifting every nucleotide after it one
+    /// position to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is greater than the length.
+    pub fn insert(&mut self, idx: usize, nuc: Nuc) {
+        assert!(
+            idx <= self.data_len,
+            "insertion index {} out of bounds for PackedDna of length {}",
+            idx,
+            self.data_len
+        );
+        // make room for one more nucleotide at the end
+        self.push(Nuc::A);
+        let first = idx / NUCS_PER_WORD;
+        let slot = idx % NUCS_PER_WORD;
+        // the word holding `idx` keeps its low slots, shifts the rest up by
+        // one slot and receives `nuc`; its top slot carries into the next
+        let word = self.data[first];
+        let low = word & mask_below(slot);
+        let mut carry = word >> 62;
+        self.data[first] =
+            low | (word & !mask_below(slot)) << 2 | u64::from(nuc.bits()) << (slot * 2);
+        for word in &mut self.data[first + 1..] {
+            let next_carry = *word >> 62;
+            *word = *word << 2 | carry;
+            carry = next_carry;
+        }
+    }
+
+    /// Returns the complement of the sequence (A <-> T, C <-> G) without
+    /// reversing it.
+    pub fn complement(&self) -> PackedDna {
+        let mut dna = self.clone();
+        dna.complement_in_place();
+        dna
+    }
+
+    /// Complements every nucleotide of the sequence in place.
+    pub fn complement_in_place(&mut self) {
+        // with A = 0, C = 1, G = 2 and T = 3 complementing flips every bit,
+        // so whole words are complemented at once
+        for word in &mut self.data {
+            *word = !*word;
+        }
+        self.clear_padding();
+    }
+
+    /// Returns the sequence in reverse order.
+    pub fn reverse(&self) -> PackedDna {
+        let mut dna = self.clone();
+        dna.reverse_in_place();
+        dna
+    }
+
+    /// Reverses the order of the nucleotides in place.
+    pub fn reverse_in_place(&mut self) {
+        // reversing the word order and the slots within every word leaves
+        // the padding of the old last word at the start, so the whole
+        // sequence is then shifted down over it
+        self.data.reverse();
+        for word in &mut self.data {
+            *word = reverse_slots(*word);
+        }
+        let pad = (NUCS_PER_WORD - self.data_len % NUCS_PER_WORD) % NUCS_PER_WORD;
+        if pad != 0 {
+            let shift = pad * 2;
+            for i in 0..self.data.len() {
+                let next = self.data.get(i + 1).map_or(0, |word| word << (64 - shift));
+                self.data[i] = self.data[i] >> shift | next;
+            }
+        }
+    }
+
+    /// Returns the reverse complement of the sequence, i.e. the sequence of
+    /// the opposite strand read 5' to 3'.
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use dna::PackedDna;
+    ///
+    /// let dna = PackedDna::from_str("AACGTG").unwrap();
+    /// assert_eq!(dna.reverse_complement().to_string(), "CACGTT");
+    /// ```
+    pub fn reverse_complement(&self) -> PackedDna {
+        let mut dna = self.clone();
+        dna.reverse_complement_in_place();
+        dna
+    }
+
+    /// Replaces the sequence with its reverse complement in place.
+    pub fn reverse_complement_in_place(&mut self) {
+        self.reverse_in_place();
+        self.complement_in_place();
+    }
+
+    /// Zeroes the bits past the end of the sequence in the last word.
+    fn clear_padding(&mut self) {
+        let slot = self.data_len % NUCS_PER_WORD;
+        if slot != 0 {
+            let last = self.data.len() - 1;
+            self.data[last] &= mask_below(slot);
+        }
+    }
+
+    /// Removes and returns the nucleotide at `idx`, shifting every
+    /// nucleotide after it one position to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    pub fn remove(&mut self, idx: usize) -> Nuc {
+        let nuc = match self.get(idx) {
+            Some(nuc) => nuc,
+            None => panic!(
+                "removal index {} out of bounds for PackedDna of length {}",
+                idx, self.data_len
+            ),
+        };
+        let first = idx / NUCS_PER_WORD;
+        let slot = idx % NUCS_PER_WORD;
+        // walk backwards so each word can pull the lowest slot of the word
+        // after it into its top slot
+        let mut carry = 0u64;
+        for word in self.data[first + 1..].iter_mut().rev() {
+            let next_carry = *word & 3;
+            *word = *word >> 2 | carry << 62;
+            carry = next_carry;
+        }
+        let word = self.data[first];
+        let low = word & mask_below(slot);
+        let high = (word >> 2) & !mask_below(slot);
+        self.data[first] = low | high | carry << 62;
+        // the last slot is now a duplicate of padding; drop it
+        let len = self.data_len - 1;
+        self.data.truncate(words_for(len));
+        self.data_len = len;
+        nuc
+    }
+}
+
+/// Formats the sequence as upper case `A`, `C`, `G` and `T` characters.
+impl Display for PackedDna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+/// This iterator function is used to iterate over the vector of Nucs
+/// and store them in the PackedDNA struct in a memory efficient manner
+/// Returns PackedDNA struct instance created using given input (vector of Nuc)
+///
+/// # Panics
+///
+/// Panics if the iterator yields more than `usize::MAX` nucleotides, which
+/// can only happen on 32-bit targets. Use [`PackedDnaBuilder`] to handle
+/// that case as an error instead.
+impl FromIterator<Nuc> for PackedDna {
+    fn from_iter<I: IntoIterator<Item = Nuc>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut builder = PackedDnaBuilder::with_capacity(iter.size_hint().0);
+        // this loops over the vector of nucs for storage
+        for nuc_data in iter {
+            if let Err(err) = builder.push(nuc_data) {
+                panic!("{}", err);
+            }
+        }
+        builder.build()
+    }
+}
+
+impl Extend<Nuc> for PackedDna {
+    fn extend<I: IntoIterator<Item = Nuc>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for nuc in iter {
+            self.push(nuc);
+        }
+    }
+}
+
+/// Incrementally builds a [`PackedDna`] one nucleotide or one run of
+/// nucleotides at a time.
+///
+/// Unlike [`FromIterator`], every append is checked and reports
+/// [`LengthOverflow`] once the sequence length no longer fits in a `usize`,
+/// so streaming inputs fail cleanly on 32-bit targets.
+///
+/// ```
+/// use dna::{Nuc, PackedDnaBuilder};
+///
+/// let mut builder = PackedDnaBuilder::new();
+/// builder.push(Nuc::G).unwrap();
+/// builder.push_run(Nuc::A, 3).unwrap();
+/// let dna = builder.build();
+/// assert_eq!(dna.len(), 4);
+/// assert_eq!(dna.get(3), Some(Nuc::A));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PackedDnaBuilder {
+    // words packed so far, with the same layout as `PackedDna::data`
+    data: Vec<u64>,
+    // number of nucleotides appended so far
+    len: usize,
+}
+
+impl PackedDnaBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> PackedDnaBuilder {
+        PackedDnaBuilder::default()
+    }
+
+    /// Creates an empty builder with room for at least `capacity`
+    /// nucleotides before reallocating.
+    pub fn with_capacity(capacity: usize) -> PackedDnaBuilder {
+        PackedDnaBuilder {
+            data: Vec::with_capacity(words_for(capacity)),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of nucleotides appended so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing has been appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a single nucleotide.
+    pub fn push(&mut self, nuc: Nuc) -> Result<(), LengthOverflow> {
+        let new_len = self.len.checked_add(1).ok_or(LengthOverflow)?;
+        // a new word is started every 32 nucleotides, the first of which
+        // goes into the low bits, matching the order `get` reads them back
+        let slot = self.len % NUCS_PER_WORD;
+        if slot == 0 {
+            self.data.push(0u64);
+        }
+        let last = self.data.len() - 1;
+        self.data[last] |= u64::from(nuc.bits()) << (slot * 2);
+        self.len = new_len;
+        Ok(())
+    }
+
+    /// Appends `count` copies of `nuc`. Whole words of the run are filled
+    /// at once, so very long runs are cheap to append.
+    pub fn push_run(&mut self, nuc: Nuc, count: usize) -> Result<(), LengthOverflow> {
+        let new_len = self.len.checked_add(count).ok_or(LengthOverflow)?;
+        let mut remaining = count;
+        // finish the partially filled last word one nucleotide at a time
+        while remaining > 0 && self.len & (NUCS_PER_WORD - 1) != 0 {
+            self.push(nuc)?;
+            remaining -= 1;
+        }
+        // the rest of the run starts on a word boundary
+        let whole_words = remaining / NUCS_PER_WORD;
+        self.data
+            .resize(self.data.len() + whole_words, fill_word(nuc));
+        self.len += whole_words * NUCS_PER_WORD;
+        for _ in 0..remaining % NUCS_PER_WORD {
+            self.push(nuc)?;
+        }
+        debug_assert_eq!(self.len, new_len);
+        Ok(())
+    }
+
+    /// Finishes building and returns the packed sequence.
+    pub fn build(self) -> PackedDna {
+        PackedDna::from_raw(self.data, self.len)
+    }
+}
+
+/// An error returned when a sequence would grow beyond `usize::MAX`
+/// nucleotides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("sequence length overflows usize")]
+pub struct LengthOverflow;
+
+/// This function is used to iterate over the DNA string containing nucleotides
+/// and store them in the PackedDNA struct in a memory efficient manner
+/// Returns PackedDNA struct instance created using given input string, or the
+/// first invalid character encountered. Use [`ParseOptions`] to report every
+/// invalid character or to accept empty input.
+impl FromStr for PackedDna {
+    type Err = ParseDnaError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ParseOptions::new().parse(s)
+    }
+}
+
+/// Options controlling how text is parsed into a [`PackedDna`].
+///
+/// The defaults match [`PackedDna::from_str`]: parsing stops at the first
+/// invalid character and empty input is rejected.
+///
+/// ```
+/// use dna::{ParseDnaError, ParseOptions};
+///
+/// let err = ParseOptions::new().collect_all(true).parse("AxGyT").unwrap_err();
+/// assert_eq!(err, ParseDnaError::InvalidSymbols(vec![(1, 'x'), (3, 'y')]));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    collect_all: bool,
+    allow_empty: bool,
+}
+
+impl ParseOptions {
+    /// Creates the default parse options.
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// When enabled, parsing continues past invalid characters and every
+    /// offending position is reported through
+    /// [`ParseDnaError::InvalidSymbols`].
+    pub fn collect_all(mut self, yes: bool) -> ParseOptions {
+        self.collect_all = yes;
+        self
+    }
+
+    /// When enabled, empty input parses to an empty [`PackedDna`] instead of
+    /// failing with [`ParseDnaError::Empty`].
+    pub fn allow_empty(mut self, yes: bool) -> ParseOptions {
+        self.allow_empty = yes;
+        self
+    }
+
+    /// Parses `s` into a [`PackedDna`] using these options. Parsing is case
+    /// insensitive and only the nucleotides A, C, G and T are accepted.
+    pub fn parse(&self, s: &str) -> Result<PackedDna, ParseDnaError> {
+        let mut builder = PackedDnaBuilder::with_capacity(s.len());
+        self.scan(s, |c| match Nuc::try_from(c) {
+            Ok(nuc) => builder.push(nuc).map(|_| true),
+            Err(_) => Ok(false),
+        })?;
+        Ok(builder.build())
+    }
+
+    /// Feeds every character of `s` to `accept`, which returns whether the
+    /// character is a valid symbol, and turns rejected characters and empty
+    /// input into errors according to these options.
+    pub(crate) fn scan<F>(&self, s: &str, mut accept: F) -> Result<(), ParseDnaError>
+    where
+        F: FnMut(char) -> Result<bool, LengthOverflow>,
+    {
+        let mut err_data = Vec::new();
+        for (offset, c) in s.char_indices() {
+            // checking if a valid nucleotide is present
+            if accept(c)? {
+                continue;
+            }
+            if !self.collect_all {
+                return Err(ParseDnaError::InvalidSymbol { offset, symbol: c });
+            }
+            err_data.push((offset, c));
+        }
+        if !err_data.is_empty() {
+            return Err(ParseDnaError::InvalidSymbols(err_data));
+        }
+        if s.is_empty() && !self.allow_empty {
+            return Err(ParseDnaError::Empty);
+        }
+        Ok(())
+    }
+}
+
+/// An error that can occur when parsing a [`PackedDna`] from text.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseDnaError {
+    /// The input contained a character that is not a nucleotide.
+    #[error("invalid nucleotide {symbol:?} at byte {offset}")]
+    InvalidSymbol {
+        /// Byte offset of the offending character in the input.
+        offset: usize,
+        /// The offending character.
+        symbol: char,
+    },
+    /// Every invalid character in the input as `(byte offset, character)`
+    /// pairs, in input order. Only returned when
+    /// [`ParseOptions::collect_all`] is enabled; the list is never empty.
+    #[error("{} invalid nucleotides in input", .0.len())]
+    InvalidSymbols(Vec<(usize, char)>),
+    /// The input was empty.
+    #[error("input DNA sequence is empty")]
+    Empty,
+    /// The input holds more nucleotides than a [`PackedDna`] can store.
+    #[error("input DNA sequence is too long")]
+    LengthOverflow,
+}
+
+impl From<LengthOverflow> for ParseDnaError {
+    fn from(_: LengthOverflow) -> ParseDnaError {
+        ParseDnaError::LengthOverflow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test to check whether FromIterator function works properly for
+    // PackedDNA struct
+    #[test]
+    fn test_dna_from_iter() {
+        // Normal input
+        assert_eq!(
+            PackedDna::from_iter(vec![Nuc::A, Nuc::C, Nuc::G, Nuc::T, Nuc::T, Nuc::T, Nuc::G]),
+            PackedDna {
+                data: vec![0x0000_0000_0000_2fe4],
+                data_len: 7
+            }
+        );
+        // Huge input
+        assert_eq!(
+            PackedDna::from_iter(vec![
+                Nuc::A,
+                Nuc::G,
+                Nuc::C,
+                Nuc::T,
+                Nuc::G,
+                Nuc::C,
+                Nuc::T,
+                Nuc::A,
+                Nuc::G,
+                Nuc::C,
+                Nuc::T,
+                Nuc::G,
+                Nuc::A,
+                Nuc::T,
+                Nuc::C,
+                Nuc::G,
+                Nuc::A,
+                Nuc::C
+            ]),
+            PackedDna {
+                data: vec![0x0000_0004_9cb6_36d8],
+                data_len: 18
+            }
+        );
+        // Empty input
+        assert_eq!(
+            PackedDna::from_iter(vec![]),
+            PackedDna {
+                data: vec![],
+                data_len: 0
+            }
+        );
+    }
+
+    // Test to check whether FromStr function works properly for
+    // PackedDNA struct
+    #[test]
+    fn test_dna_from_str() {
+        // Normal Input
+        let res = PackedDna::from_str("ACGTTT").unwrap();
+        assert_eq!(
+            res,
+            PackedDna {
+                data: vec![0x0000_0000_0000_0fe4],
+                data_len: 6
+            }
+        );
+        // Huge Input
+        let res2 = PackedDna::from_str("AGCTGCTAGCTGATCGAAGTCAAAAAgggggtgAattttttttttttttttttttttgatgatcgtgacgtagtcgtacttagcta").unwrap();
+        assert_eq!(
+            res2,
+            PackedDna {
+                data: vec![
+                    0xeaa0_01e0_9cb6_36d8,
+                    0x72cb_ffff_ffff_ffc2,
+                    0x0000_0363_d39e_392e,
+                ],
+                data_len: 86
+            }
+        );
+        // Empty Input
+        assert_eq!(PackedDna::from_str(""), Err(ParseDnaError::Empty));
+        let res3 = ParseOptions::new().allow_empty(true).parse("").unwrap();
+        assert_eq!(
+            res3,
+            PackedDna {
+                data: vec![],
+                data_len: 0
+            }
+        );
+    }
+
+    // Test to check that `get` reads back exactly what `FromIterator` and
+    // `FromStr` wrote, for every length modulo 4 and across word boundaries
+    #[test]
+    fn test_dna_get_roundtrip() {
+        let pattern = [Nuc::T, Nuc::G, Nuc::A, Nuc::C, Nuc::C, Nuc::A, Nuc::T];
+        for len in 0..=70 {
+            let nucs: Vec<Nuc> = pattern.iter().cycle().take(len).copied().collect();
+            let text: String = "TGACCAT".chars().cycle().take(len).collect();
+            let from_iter = PackedDna::from_iter(nucs.clone());
+            let from_str = ParseOptions::new().allow_empty(true).parse(&text).unwrap();
+            assert_eq!(from_iter, from_str);
+            assert_eq!(from_iter.len(), len);
+            for (idx, nuc) in nucs.iter().enumerate() {
+                assert_eq!(from_iter.get(idx), Some(*nuc));
+                assert_eq!(unsafe { from_iter.get_unchecked(idx) }, *nuc);
+            }
+            assert_eq!(from_iter.get(len), None);
+        }
+    }
+
+    // Test to check that out of bounds indices are rejected rather than
+    // reading the padding bits of the last byte
+    #[test]
+    fn test_dna_get_out_of_bounds() {
+        let dna = PackedDna::from_str("ACG").unwrap();
+        assert_eq!(dna.get(2), Some(Nuc::G));
+        assert_eq!(dna.get(3), None);
+        assert_eq!(dna.get(usize::MAX), None);
+        assert_eq!(PackedDna::from_iter(vec![]).get(0), None);
+    }
+
+    // Test to check that invalid input is reported through `Result` with
+    // the byte offset of the offending characters
+    #[test]
+    fn test_dna_from_str_errors() {
+        assert_eq!(
+            PackedDna::from_str("ACGNTX"),
+            Err(ParseDnaError::InvalidSymbol {
+                offset: 3,
+                symbol: 'N'
+            })
+        );
+        // offsets are byte offsets, so multi-byte characters shift them
+        assert_eq!(
+            PackedDna::from_str("Aé-T"),
+            Err(ParseDnaError::InvalidSymbol {
+                offset: 1,
+                symbol: 'é'
+            })
+        );
+        assert_eq!(
+            ParseOptions::new().collect_all(true).parse("Aé-T"),
+            Err(ParseDnaError::InvalidSymbols(vec![(1, 'é'), (3, '-')]))
+        );
+        assert_eq!(
+            ParseOptions::new().collect_all(true).parse("acgt"),
+            PackedDna::from_str("ACGT")
+        );
+        assert_eq!(
+            PackedDna::from_str("ACGNTX").unwrap_err().to_string(),
+            "invalid nucleotide 'N' at byte 3"
+        );
+    }
+
+    // Test to check that push and pop behave like a stack across word
+    // boundaries and leave the padding bits zeroed
+    #[test]
+    fn test_dna_push_pop() {
+        let nucs: Vec<Nuc> = "GATTACA"
+            .repeat(10)
+            .chars()
+            .map(|c| Nuc::try_from(c).unwrap())
+            .collect();
+        let mut dna = PackedDna::new();
+        for nuc in &nucs {
+            dna.push(*nuc);
+        }
+        assert_eq!(dna, PackedDna::from_iter(nucs.clone()));
+        let mut model = nucs;
+        while let Some(nuc) = dna.pop() {
+            assert_eq!(Some(nuc), model.pop());
+            assert_eq!(dna, PackedDna::from_iter(model.clone()));
+        }
+        assert!(model.is_empty());
+        assert_eq!(dna, PackedDna::new());
+    }
+
+    // Test to check set, truncate and extend against a plain vector
+    #[test]
+    fn test_dna_set_truncate_extend() {
+        let mut dna = PackedDna::from_str(&"ACGT".repeat(20)).unwrap();
+        dna.set(0, Nuc::T);
+        dna.set(33, Nuc::A);
+        dna.set(79, Nuc::C);
+        assert_eq!(dna.get(0), Some(Nuc::T));
+        assert_eq!(dna.get(1), Some(Nuc::C));
+        assert_eq!(dna.get(33), Some(Nuc::A));
+        assert_eq!(dna.get(79), Some(Nuc::C));
+        dna.truncate(34);
+        assert_eq!(dna.len(), 34);
+        dna.extend(vec![Nuc::G, Nuc::G]);
+        let mut expected = "ACGT".repeat(9)[..34].to_string();
+        expected.replace_range(0..1, "T");
+        expected.replace_range(33..34, "A");
+        expected.push_str("GG");
+        assert_eq!(dna, PackedDna::from_str(&expected).unwrap());
+        dna.truncate(100);
+        assert_eq!(dna.len(), 36);
+    }
+
+    // Test to check insert and remove at every position, including those
+    // that carry nucleotides across word boundaries
+    #[test]
+    fn test_dna_insert_remove() {
+        let nucs: Vec<Nuc> = "ACGGTCA"
+            .repeat(10)
+            .chars()
+            .map(|c| Nuc::try_from(c).unwrap())
+            .collect();
+        for len in [0, 1, 31, 32, 33, 64, 70] {
+            let base = PackedDna::from_iter(nucs[..len].iter().copied());
+            for idx in 0..=len {
+                let mut dna = base.clone();
+                dna.insert(idx, Nuc::T);
+                let mut model = nucs[..len].to_vec();
+                model.insert(idx, Nuc::T);
+                assert_eq!(dna, PackedDna::from_iter(model));
+                assert_eq!(dna.remove(idx), Nuc::T);
+                assert_eq!(dna, base);
+                if idx < len {
+                    let mut dna = base.clone();
+                    let mut expected = nucs[..len].to_vec();
+                    assert_eq!(dna.remove(idx), expected.remove(idx));
+                    assert_eq!(dna, PackedDna::from_iter(expected));
+                }
+            }
+        }
+    }
+
+    // Test to check complement, reverse and reverse complement against
+    // string manipulation, for lengths that end anywhere within a word
+    #[test]
+    fn test_dna_reverse_complement() {
+        let text = "GATTACACCGTAGGCTTAACGTTGCAGTCCAGTAGGATCCATCGATCGAAATTCGGCTAGCTAGCA";
+        let complement = |c: char| match c {
+            'A' => 'T',
+            'C' => 'G',
+            'G' => 'C',
+            _ => 'A',
+        };
+        for len in 0..=text.len() {
+            let dna = ParseOptions::new()
+                .allow_empty(true)
+                .parse(&text[..len])
+                .unwrap();
+            let comp: String = text[..len].chars().map(complement).collect();
+            let rev: String = text[..len].chars().rev().collect();
+            let revcomp: String = text[..len].chars().rev().map(complement).collect();
+            assert_eq!(dna.complement().to_string(), comp);
+            assert_eq!(dna.reverse().to_string(), rev);
+            assert_eq!(dna.reverse_complement().to_string(), revcomp);
+            // padding must stay zeroed for equality to hold
+            assert_eq!(dna.reverse(), PackedDna::from_iter(dna.iter().rev()));
+            assert_eq!(
+                dna.reverse_complement(),
+                PackedDna::from_iter(dna.iter().rev().map(Nuc::complement))
+            );
+            let mut in_place = dna.clone();
+            in_place.reverse_complement_in_place();
+            in_place.reverse_complement_in_place();
+            assert_eq!(in_place, dna);
+        }
+    }
+
+    // Test to check capacity management
+    #[test]
+    fn test_dna_capacity() {
+        let mut dna = PackedDna::with_capacity(100);
+        assert!(dna.capacity() >= 100);
+        assert!(dna.is_empty());
+        dna.extend(vec![Nuc::A; 10]);
+        dna.reserve(500);
+        assert!(dna.capacity() >= 510);
+        dna.shrink_to_fit();
+        assert!(dna.capacity() >= 10);
+        assert_eq!(dna.len(), 10);
+    }
+
+    // Test to check that runs appended through the builder pack the same
+    // way as individual nucleotides, whatever the alignment of the run
+    #[test]
+    fn test_builder_push_run() {
+        for prefix in 0..4 {
+            for count in 0..10 {
+                let mut builder = PackedDnaBuilder::new();
+                builder.push_run(Nuc::C, prefix).unwrap();
+                builder.push_run(Nuc::G, count).unwrap();
+                builder.push(Nuc::T).unwrap();
+                let mut nucs = vec![Nuc::C; prefix];
+                nucs.extend(vec![Nuc::G; count]);
+                nucs.push(Nuc::T);
+                let expected = PackedDna::from_iter(nucs);
+                assert_eq!(builder.len(), prefix + count + 1);
+                assert_eq!(builder.build(), expected);
+            }
+        }
+        let mut builder = PackedDnaBuilder::new();
+        builder.push(Nuc::A).unwrap();
+        assert_eq!(builder.push_run(Nuc::A, usize::MAX), Err(LengthOverflow));
+        assert_eq!(builder.len(), 1);
+    }
+
+    // Test to check that clearing a range zeroes exactly the positions in
+    // it, within one word, across words and on word boundaries
+    #[test]
+    fn test_dna_clear_range() {
+        let text = "ACGTTGCAGGTCCATG".repeat(7);
+        let dna = PackedDna::from_str(&text).unwrap();
+        for (start, end) in [
+            (0, 0),
+            (3, 9),
+            (0, 32),
+            (5, 64),
+            (31, 33),
+            (30, 100),
+            (64, 112),
+        ] {
+            let mut cleared = dna.clone();
+            cleared.clear_range(start..end);
+            let expected: String = text
+                .chars()
+                .enumerate()
+                .map(|(idx, c)| if (start..end).contains(&idx) { 'A' } else { c })
+                .collect();
+            assert_eq!(cleared.to_string(), expected, "{}..{}", start, end);
+        }
+    }
+
+    // Test to check the word and slot arithmetic around the 4 Gbp boundary
+    // without allocating a sequence that long
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_locate_past_u32() {
+        let boundary = u64::from(u32::MAX);
+        for pos in boundary - 40..boundary + 40 {
+            let (word, shift) = locate(pos as usize);
+            assert_eq!(word as u64, pos / 32);
+            assert_eq!(shift as u64, (pos % 32) * 2);
+            assert_eq!(
+                words_for(pos as usize) as u64,
+                pos / 32 + u64::from(pos & 31 != 0)
+            );
+        }
+        // a nucleotide just past the boundary read from a two word window
+        // placed at the word `locate` reports
+        let (word, shift) = locate(boundary as usize + 2);
+        assert_eq!((word, shift), (1 << 27, 2));
+        let window = [fill_word(Nuc::C), fill_word(Nuc::G)];
+        let at = |pos: usize| word_at(&window, pos - word * NUCS_PER_WORD) & 3;
+        assert_eq!(at(boundary as usize + 2), u64::from(Nuc::C.bits()));
+        assert_eq!(at(boundary as usize + 32), u64::from(Nuc::C.bits()));
+        assert_eq!(at(boundary as usize + 33), u64::from(Nuc::G.bits()));
+    }
+
+    // Test to check that sequences longer than `u32::MAX` bases keep their
+    // length and stay addressable past the 4 Gbp boundary; run it with
+    // `cargo test -- --ignored` on a machine with memory to spare
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[ignore = "allocates about 1 GiB"]
+    fn test_dna_longer_than_u32() {
+        let boundary = u32::MAX as usize;
+        let mut builder = PackedDnaBuilder::new();
+        builder.push_run(Nuc::A, boundary - 1).unwrap();
+        for nuc in [Nuc::C, Nuc::G, Nuc::T, Nuc::C] {
+            builder.push(nuc).unwrap();
+        }
+        let dna = builder.build();
+        assert_eq!(dna.len(), boundary + 3);
+        assert_eq!(dna.get(boundary - 2), Some(Nuc::A));
+        assert_eq!(dna.get(boundary - 1), Some(Nuc::C));
+        assert_eq!(dna.get(boundary), Some(Nuc::G));
+        assert_eq!(dna.get(boundary + 1), Some(Nuc::T));
+        assert_eq!(dna.get(boundary + 2), Some(Nuc::C));
+        assert_eq!(dna.get(boundary + 3), None);
+    }
+}
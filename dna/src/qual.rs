@@ -0,0 +1,185 @@
+//! [`PackedQual`], compact storage for per-base quality scores.
+
+use std::iter::FromIterator;
+
+// the value every bin decodes to and the lowest score that falls into the
+// next bin, following Illumina's eight level binning scheme (2-9 to 6,
+// 10-19 to 15, 20-24 to 22, 25-29 to 27, 30-34 to 33, 35-39 to 37, 40 and
+// up to 40); scores 0 and 1 fall outside its table, so they keep a bin of
+// their own that decodes to 0 to make binning idempotent
+const BIN_VALUES: [u8; 8] = [0, 6, 15, 22, 27, 33, 37, 40];
+const BIN_LIMITS: [u8; 7] = [2, 10, 20, 25, 30, 35, 40];
+
+/// Returns the Illumina bin index of a Phred `score`.
+fn bin_index(score: u8) -> u8 {
+    BIN_LIMITS
+        .iter()
+        .take_while(|&&limit| score >= limit)
+        .count() as u8
+}
+
+/// Per-base Phred quality scores.
+///
+/// By default every score is kept exactly, one byte each. Binned qualities
+/// instead round every score to one of the seven levels Illumina
+/// instruments report (6, 15, 22, 27, 33, 37 and 40), or to 0 for scores
+/// below 2, and pack two scores per byte, which is enough for most
+/// downstream tools and halves the memory again.
+///
+/// ```
+/// use dna::PackedQual;
+///
+/// let exact: PackedQual = vec![40, 38, 12, 2].into_iter().collect();
+/// assert_eq!(exact.get(1), Some(38));
+///
+/// let binned = exact.to_binned();
+/// assert!(binned.is_binned());
+/// assert_eq!(binned.iter().collect::<Vec<_>>(), [40, 37, 15, 6]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PackedQual {
+    // scores, or bin indices packed two per byte from the low nibble with
+    // an unused high nibble left at zero
+    data: Vec<u8>,
+    len: usize,
+    binned: bool,
+}
+
+impl PackedQual {
+    /// Creates an empty set of exact qualities.
+    pub fn new() -> PackedQual {
+        PackedQual::default()
+    }
+
+    /// Creates an empty set of binned qualities.
+    pub fn new_binned() -> PackedQual {
+        PackedQual {
+            binned: true,
+            ..PackedQual::default()
+        }
+    }
+
+    /// Rounds a Phred `score` to its Illumina bin level.
+    pub fn illumina_bin(score: u8) -> u8 {
+        BIN_VALUES[bin_index(score) as usize]
+    }
+
+    /// Returns `true` if the scores are binned.
+    pub fn is_binned(&self) -> bool {
+        self.binned
+    }
+
+    /// Returns the number of scores.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no scores.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the score at `idx`, or `None` if `idx` is out of bounds.
+    /// Binned scores are reported as their bin level.
+    pub fn get(&self, idx: usize) -> Option<u8> {
+        if idx >= self.len {
+            return None;
+        }
+        if self.binned {
+            let nibble = (self.data[idx / 2] >> ((idx % 2) * 4)) & 0xf;
+            Some(BIN_VALUES[nibble as usize])
+        } else {
+            Some(self.data[idx])
+        }
+    }
+
+    /// Appends a score, rounding it to its bin level if the scores are
+    /// binned.
+    pub fn push(&mut self, score: u8) {
+        if !self.binned {
+            self.data.push(score);
+        } else if self.len & 1 == 0 {
+            self.data.push(bin_index(score));
+        } else {
+            *self.data.last_mut().expect("half filled byte") |= bin_index(score) << 4;
+        }
+        self.len += 1;
+    }
+
+    /// Returns an iterator over the scores.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = u8> + ExactSizeIterator + '_ {
+        (0..self.len).map(move |idx| self.get(idx).expect("index within bounds"))
+    }
+
+    /// Returns a binned copy of the scores.
+    pub fn to_binned(&self) -> PackedQual {
+        let mut binned = PackedQual::new_binned();
+        binned.data.reserve(self.len / 2 + 1);
+        for score in self.iter() {
+            binned.push(score);
+        }
+        binned
+    }
+}
+
+/// Collects exact scores.
+impl FromIterator<u8> for PackedQual {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let data: Vec<u8> = iter.into_iter().collect();
+        PackedQual {
+            len: data.len(),
+            data,
+            binned: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test to check the bin boundaries and that binned storage packs two
+    // scores per byte
+    #[test]
+    fn test_binning() {
+        let expected = [
+            (0, 0),
+            (1, 0),
+            (2, 6),
+            (3, 6),
+            (9, 6),
+            (10, 15),
+            (19, 15),
+            (20, 22),
+            (24, 22),
+            (25, 27),
+            (29, 27),
+            (30, 33),
+            (34, 33),
+            (35, 37),
+            (39, 37),
+            (40, 40),
+            (41, 40),
+            (93, 40),
+        ];
+        for &(score, level) in &expected {
+            assert_eq!(PackedQual::illumina_bin(score), level, "score {}", score);
+        }
+        let scores: Vec<u8> = (0..=60).collect();
+        let exact: PackedQual = scores.iter().copied().collect();
+        assert_eq!(exact.iter().collect::<Vec<_>>(), scores);
+        let binned = exact.to_binned();
+        assert_eq!(binned.len(), scores.len());
+        assert_eq!(binned.data.len(), 31);
+        assert_eq!(
+            binned.iter().rev().collect::<Vec<_>>(),
+            scores
+                .iter()
+                .rev()
+                .map(|&score| PackedQual::illumina_bin(score))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(binned.get(61), None);
+        assert_eq!(binned.to_binned(), binned);
+    }
+}
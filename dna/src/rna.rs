@@ -0,0 +1,290 @@
+//! RNA nucleotides and [`PackedRna`], which shares the two bit storage of
+//! [`PackedDna`] with U in place of T.
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display, Write},
+    iter::FromIterator,
+    str::FromStr,
+};
+
+use crate::{
+    Nuc, NucCounts, PackedDna, PackedDnaBuilder, ParseDnaError, ParseNucError, ParseOptions,
+};
+
+/// An RNA nucleotide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rna {
+    /// Adenine
+    A,
+    /// Cytosine
+    C,
+    /// Guanine
+    G,
+    /// Uracil
+    U,
+}
+
+impl Rna {
+    /// Returns the Watson-Crick complement of the nucleotide (A <-> U,
+    /// C <-> G).
+    pub fn complement(self) -> Rna {
+        Rna::from(Nuc::from(self).complement())
+    }
+}
+
+/// Maps U to T and keeps the other nucleotides.
+impl From<Rna> for Nuc {
+    fn from(rna: Rna) -> Nuc {
+        match rna {
+            Rna::A => Nuc::A,
+            Rna::C => Nuc::C,
+            Rna::G => Nuc::G,
+            Rna::U => Nuc::T,
+        }
+    }
+}
+
+/// Maps T to U and keeps the other nucleotides.
+impl From<Nuc> for Rna {
+    fn from(nuc: Nuc) -> Rna {
+        match nuc {
+            Nuc::A => Rna::A,
+            Nuc::C => Rna::C,
+            Nuc::G => Rna::G,
+            Nuc::T => Rna::U,
+        }
+    }
+}
+
+impl From<Rna> for char {
+    fn from(rna: Rna) -> char {
+        match rna {
+            Rna::A => 'A',
+            Rna::C => 'C',
+            Rna::G => 'G',
+            Rna::U => 'U',
+        }
+    }
+}
+
+impl Display for Rna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+impl TryFrom<char> for Rna {
+    type Error = ParseNucError<char>;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value.to_ascii_uppercase() {
+            'A' => Ok(Self::A),
+            'C' => Ok(Self::C),
+            'G' => Ok(Self::G),
+            'U' => Ok(Self::U),
+            _ => Err(ParseNucError(value)),
+        }
+    }
+}
+
+impl FromStr for Rna {
+    type Err = ParseNucError<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.to_ascii_uppercase();
+        match upper.as_str() {
+            "A" => Ok(Self::A),
+            "C" => Ok(Self::C),
+            "G" => Ok(Self::G),
+            "U" => Ok(Self::U),
+            _ => Err(ParseNucError(upper)),
+        }
+    }
+}
+
+/// A packed RNA sequence.
+///
+/// The sequence is stored exactly like a [`PackedDna`], with U taking the
+/// code of T, so [`PackedDna::transcribe`] and
+/// [`PackedRna::reverse_transcribe`] hand the storage over without touching
+/// it.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{PackedDna, PackedRna, Rna};
+///
+/// let rna = PackedDna::from_str("GATTACA").unwrap().transcribe();
+/// assert_eq!(rna.get(2), Some(Rna::U));
+/// assert_eq!(rna.to_string(), "GAUUACA");
+/// assert_eq!(rna, PackedRna::from_str("gauuaca").unwrap());
+/// assert_eq!(rna.reverse_transcribe().to_string(), "GATTACA");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PackedRna {
+    dna: PackedDna,
+}
+
+impl PackedRna {
+    /// Creates an empty sequence.
+    pub fn new() -> PackedRna {
+        PackedRna::default()
+    }
+
+    /// Creates an empty sequence with room for at least `capacity`
+    /// nucleotides.
+    pub fn with_capacity(capacity: usize) -> PackedRna {
+        PackedRna {
+            dna: PackedDna::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of nucleotides in the sequence.
+    pub fn len(&self) -> usize {
+        self.dna.len()
+    }
+
+    /// Returns `true` if the sequence holds no nucleotides.
+    pub fn is_empty(&self) -> bool {
+        self.dna.is_empty()
+    }
+
+    /// Returns the nucleotide at `idx`, or `None` if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<Rna> {
+        self.dna.get(idx).map(Rna::from)
+    }
+
+    /// Appends a nucleotide to the end of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length would overflow `usize`.
+    pub fn push(&mut self, rna: Rna) {
+        self.dna.push(Nuc::from(rna))
+    }
+
+    /// Returns an iterator over the nucleotides of the sequence.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Rna> + ExactSizeIterator + '_ {
+        self.dna.iter().map(Rna::from)
+    }
+
+    /// Counts the nucleotides of the sequence. U is reported as
+    /// [`Nuc::T`].
+    pub fn counts(&self) -> NucCounts {
+        self.dna.counts()
+    }
+
+    /// Returns the reverse complement of the sequence.
+    pub fn reverse_complement(&self) -> PackedRna {
+        PackedRna {
+            dna: self.dna.reverse_complement(),
+        }
+    }
+
+    /// Converts the sequence back to DNA, turning every U into T. The packed
+    /// storage is reused as is.
+    pub fn reverse_transcribe(self) -> PackedDna {
+        self.dna
+    }
+}
+
+impl PackedDna {
+    /// Converts the sequence to RNA, turning every T into U. The packed
+    /// storage is reused as is.
+    pub fn transcribe(self) -> PackedRna {
+        PackedRna { dna: self }
+    }
+}
+
+impl FromIterator<Rna> for PackedRna {
+    fn from_iter<I: IntoIterator<Item = Rna>>(iter: I) -> Self {
+        PackedDna::from_iter(iter.into_iter().map(Nuc::from)).transcribe()
+    }
+}
+
+impl ParseOptions {
+    /// Parses `s` into a [`PackedRna`] using these options. Parsing is case
+    /// insensitive and only the nucleotides A, C, G and U are accepted.
+    pub fn parse_rna(&self, s: &str) -> Result<PackedRna, ParseDnaError> {
+        let mut builder = PackedDnaBuilder::with_capacity(s.len());
+        self.scan(s, |c| match Rna::try_from(c) {
+            Ok(rna) => builder.push(Nuc::from(rna)).map(|_| true),
+            Err(_) => Ok(false),
+        })?;
+        Ok(builder.build().transcribe())
+    }
+}
+
+/// Parses a case insensitive string of A, C, G and U, reporting errors the
+/// same way [`PackedDna::from_str`] does.
+impl FromStr for PackedRna {
+    type Err = ParseDnaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ParseOptions::new().parse_rna(s)
+    }
+}
+
+impl Display for PackedRna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rna in self.iter() {
+            f.write_char(char::from(rna))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test to check the conversions between the RNA and DNA nucleotides
+    #[test]
+    fn test_rna_nuc() {
+        for (rna, nuc, c) in [
+            (Rna::A, Nuc::A, 'A'),
+            (Rna::C, Nuc::C, 'C'),
+            (Rna::G, Nuc::G, 'G'),
+            (Rna::U, Nuc::T, 'U'),
+        ] {
+            assert_eq!(Nuc::from(rna), nuc);
+            assert_eq!(Rna::from(nuc), rna);
+            assert_eq!(char::from(rna), c);
+            assert_eq!(Rna::try_from(c.to_ascii_lowercase()).unwrap(), rna);
+            assert_eq!(Rna::from_str(&rna.to_string()).unwrap(), rna);
+            assert_eq!(rna.complement(), Rna::from(nuc.complement()));
+        }
+        assert!(Rna::try_from('T').is_err());
+        assert!(Rna::from_str("UU").is_err());
+    }
+
+    // Test to check parsing, transcription and the sequence operations of
+    // PackedRna
+    #[test]
+    fn test_packed_rna() {
+        let text = "AUGGCCAUUGUAAUGGGCCGCUGAAAGGGUGCCCGAUAG";
+        let rna = PackedRna::from_str(text).unwrap();
+        assert_eq!(rna.len(), text.len());
+        assert_eq!(rna.to_string(), text);
+        assert_eq!(rna.iter().rev().map(char::from).collect::<String>(), {
+            text.chars().rev().collect::<String>()
+        });
+        let dna = rna.clone().reverse_transcribe();
+        assert_eq!(dna.to_string(), text.replace('U', "T"));
+        assert_eq!(dna.clone().transcribe(), rna);
+        assert_eq!(rna.counts(), dna.counts());
+        assert_eq!(
+            rna.reverse_complement().reverse_transcribe(),
+            dna.reverse_complement()
+        );
+        assert_eq!(rna.iter().collect::<PackedRna>(), rna);
+        assert_eq!(
+            PackedRna::from_str("ACGT"),
+            Err(ParseDnaError::InvalidSymbol {
+                offset: 3,
+                symbol: 'T'
+            })
+        );
+        assert_eq!(PackedRna::from_str(""), Err(ParseDnaError::Empty));
+    }
+}
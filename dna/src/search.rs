@@ -0,0 +1,378 @@
+//! Exact pattern search over packed sequences.
+
+use crate::{
+    packed::{PackedDna, NUCS_PER_WORD},
+    PackedDnaSlice, Strand,
+};
+
+/// An occurrence of a pattern found by [`SearchOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Match {
+    /// Start of the occurrence on the forward strand. It spans as many
+    /// nucleotides as the pattern.
+    pub start: usize,
+    /// The strand the pattern was found on. A reverse strand match means
+    /// the reverse complement of the pattern occurs at `start` on the
+    /// forward strand.
+    pub strand: Strand,
+}
+
+/// Options controlling how patterns are searched for.
+///
+/// The search slides a window of up to 32 nucleotides over the text,
+/// shifting each new nucleotide straight out of the packed words, and
+/// compares the window with the pattern as a single word. Longer patterns
+/// are keyed on their first 32 nucleotides and the rest is compared a word
+/// at a time. Matches may overlap, and an empty pattern matches at every
+/// position.
+///
+/// By default only the forward strand is searched.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{PackedDna, SearchOptions, Strand};
+///
+/// let text = PackedDna::from_str("GGATCCAAGGTACCTT").unwrap();
+/// let primer = PackedDna::from_str("GGTACC").unwrap();
+/// assert_eq!(text.find(&primer), Some(8));
+///
+/// let site = PackedDna::from_str("AAGGT").unwrap();
+/// let both = SearchOptions::new().both_strands(true);
+/// let matches: Vec<_> = both
+///     .find_iter(text.as_slice(), site.as_slice())
+///     .map(|m| (m.start, m.strand))
+///     .collect();
+/// // ACCTT, the reverse complement of AAGGT, starts at 11
+/// assert_eq!(matches, [(6, Strand::Forward), (11, Strand::Reverse)]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SearchOptions {
+    both_strands: bool,
+}
+
+impl SearchOptions {
+    /// Creates the default search options.
+    pub fn new() -> SearchOptions {
+        SearchOptions::default()
+    }
+
+    /// When enabled, the reverse strand is searched as well, by looking for
+    /// the reverse complement of the pattern. A pattern that is its own
+    /// reverse complement is reported once on each strand.
+    pub fn both_strands(mut self, yes: bool) -> SearchOptions {
+        self.both_strands = yes;
+        self
+    }
+
+    /// Returns an iterator over the occurrences of `pattern` in `text`, in
+    /// order of their start, forward strand first at equal starts.
+    pub fn find_iter<'a>(
+        &self,
+        text: PackedDnaSlice<'a>,
+        pattern: PackedDnaSlice<'a>,
+    ) -> Matches<'a> {
+        let reverse = if self.both_strands && !pattern.is_empty() {
+            Some(pattern.reverse_complement())
+        } else {
+            None
+        };
+        let key = Key::new(pattern, reverse.as_ref());
+        Matches {
+            text,
+            pattern,
+            reverse,
+            key,
+            window: 0,
+            pos: 0,
+            pending: None,
+        }
+    }
+
+    /// Returns the first occurrence of `pattern` in `text`.
+    pub fn find(&self, text: PackedDnaSlice<'_>, pattern: PackedDnaSlice<'_>) -> Option<Match> {
+        self.find_iter(text, pattern).next()
+    }
+
+    /// Returns the last occurrence of `pattern` in `text`, searching from
+    /// the end. At equal starts the reverse strand match comes last.
+    pub fn rfind(&self, text: PackedDnaSlice<'_>, pattern: PackedDnaSlice<'_>) -> Option<Match> {
+        let len = pattern.len();
+        if len > text.len() {
+            return None;
+        }
+        if len == 0 {
+            return Some(Match {
+                start: text.len(),
+                strand: Strand::Forward,
+            });
+        }
+        let reverse = if self.both_strands {
+            Some(pattern.reverse_complement())
+        } else {
+            None
+        };
+        let key = Key::new(pattern, reverse.as_ref());
+        let mask = if key.len == NUCS_PER_WORD {
+            !0
+        } else {
+            (1u64 << (2 * key.len)) - 1
+        };
+        let mut window = 0u64;
+        let mut word = 0u64;
+        // shift nucleotides in at the low end, from the last one backwards,
+        // so the window holds the `key.len` nucleotides starting at `pos`
+        for pos in (0..text.len()).rev() {
+            let slot = pos & (NUCS_PER_WORD - 1);
+            if slot == NUCS_PER_WORD - 1 || pos == text.len() - 1 {
+                word = text.word_at(pos - slot);
+            }
+            window = ((window << 2) | ((word >> (2 * slot)) & 3)) & mask;
+            if pos + len > text.len() {
+                continue;
+            }
+            let reverse = reverse.as_ref().map(PackedDna::as_slice);
+            if key.reverse == Some(window) && verify(text, pos, reverse.expect("both strands")) {
+                return Some(Match {
+                    start: pos,
+                    strand: Strand::Reverse,
+                });
+            }
+            if key.forward == window && verify(text, pos, pattern) {
+                return Some(Match {
+                    start: pos,
+                    strand: Strand::Forward,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// The first nucleotides of a pattern, and of its reverse complement,
+/// packed into a word to compare with the sliding window.
+#[derive(Debug, Clone)]
+struct Key {
+    len: usize,
+    forward: u64,
+    reverse: Option<u64>,
+}
+
+impl Key {
+    fn new(pattern: PackedDnaSlice<'_>, reverse: Option<&PackedDna>) -> Key {
+        let len = pattern.len().min(NUCS_PER_WORD);
+        let first = |seq: PackedDnaSlice<'_>| seq.slice(..len).words().next().unwrap_or(0);
+        Key {
+            len,
+            forward: first(pattern),
+            reverse: reverse.map(|reverse| first(reverse.as_slice())),
+        }
+    }
+}
+
+/// Checks the nucleotides of `pattern` past its key against `text` at
+/// `start`.
+fn verify(text: PackedDnaSlice<'_>, start: usize, pattern: PackedDnaSlice<'_>) -> bool {
+    pattern.len() <= NUCS_PER_WORD
+        || text
+            .slice(start + NUCS_PER_WORD..start + pattern.len())
+            .words()
+            .eq(pattern.slice(NUCS_PER_WORD..).words())
+}
+
+/// An iterator over the occurrences of a pattern, returned by
+/// [`SearchOptions::find_iter`].
+#[derive(Debug, Clone)]
+pub struct Matches<'a> {
+    text: PackedDnaSlice<'a>,
+    pattern: PackedDnaSlice<'a>,
+    // the reverse complement of the pattern, when searching both strands
+    reverse: Option<PackedDna>,
+    key: Key,
+    // the last `key.len` nucleotides shifted in, the oldest in the low bits
+    window: u64,
+    // the next position of the text to shift into the window
+    pos: usize,
+    // a reverse strand match at the same start as the last forward one
+    pending: Option<Match>,
+}
+
+impl Iterator for Matches<'_> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        if let Some(found) = self.pending.take() {
+            return Some(found);
+        }
+        let (len, text_len) = (self.pattern.len(), self.text.len());
+        if len == 0 {
+            if self.pos > text_len {
+                return None;
+            }
+            self.pos += 1;
+            return Some(Match {
+                start: self.pos - 1,
+                strand: Strand::Forward,
+            });
+        }
+        let shift = 2 * (self.key.len - 1);
+        // the word holding `pos`, aligned to a multiple of 32 within the view
+        let mut word = self.text.word_at(self.pos & !(NUCS_PER_WORD - 1));
+        while self.pos < text_len {
+            let slot = self.pos & (NUCS_PER_WORD - 1);
+            if slot == 0 {
+                word = self.text.word_at(self.pos);
+            }
+            let nuc = (word >> (2 * slot)) & 3;
+            self.window = (self.window >> 2) | (nuc << shift);
+            self.pos += 1;
+            if self.pos < self.key.len {
+                continue;
+            }
+            let start = self.pos - self.key.len;
+            if start + len > text_len {
+                // every later start runs off the end as well
+                self.pos = text_len;
+                break;
+            }
+            let forward = self.window == self.key.forward && verify(self.text, start, self.pattern);
+            let reverse = self.key.reverse == Some(self.window)
+                && verify(
+                    self.text,
+                    start,
+                    self.reverse.as_ref().expect("both strands").as_slice(),
+                );
+            let found = |strand| Match { start, strand };
+            match (forward, reverse) {
+                (true, true) => {
+                    self.pending = Some(found(Strand::Reverse));
+                    return Some(found(Strand::Forward));
+                }
+                (true, false) => return Some(found(Strand::Forward)),
+                (false, true) => return Some(found(Strand::Reverse)),
+                (false, false) => {}
+            }
+        }
+        None
+    }
+}
+
+impl PackedDna {
+    /// Returns the start of the first occurrence of `pattern` on the
+    /// forward strand, see [`SearchOptions`].
+    pub fn find(&self, pattern: &PackedDna) -> Option<usize> {
+        self.as_slice().find(pattern.as_slice())
+    }
+
+    /// Returns the start of the last occurrence of `pattern` on the forward
+    /// strand.
+    pub fn rfind(&self, pattern: &PackedDna) -> Option<usize> {
+        self.as_slice().rfind(pattern.as_slice())
+    }
+
+    /// Returns an iterator over the starts of every occurrence of `pattern`
+    /// on the forward strand, overlapping ones included.
+    pub fn find_iter<'a>(&'a self, pattern: &'a PackedDna) -> impl Iterator<Item = usize> + 'a {
+        self.as_slice().find_iter(pattern.as_slice())
+    }
+}
+
+impl<'a> PackedDnaSlice<'a> {
+    /// Returns the start of the first occurrence of `pattern` within the
+    /// view, the same way [`PackedDna::find`] does.
+    pub fn find(&self, pattern: PackedDnaSlice<'_>) -> Option<usize> {
+        SearchOptions::new()
+            .find(*self, pattern)
+            .map(|found| found.start)
+    }
+
+    /// Returns the start of the last occurrence of `pattern` within the
+    /// view.
+    pub fn rfind(&self, pattern: PackedDnaSlice<'_>) -> Option<usize> {
+        SearchOptions::new()
+            .rfind(*self, pattern)
+            .map(|found| found.start)
+    }
+
+    /// Returns an iterator over the starts of every occurrence of `pattern`
+    /// within the view.
+    pub fn find_iter<'p>(&self, pattern: PackedDnaSlice<'p>) -> impl Iterator<Item = usize> + 'p
+    where
+        'a: 'p,
+    {
+        SearchOptions::new()
+            .find_iter(*self, pattern)
+            .map(|found| found.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Finds every occurrence by comparing strings, for reference.
+    fn naive(text: &str, pattern: &str) -> Vec<usize> {
+        (0..=text.len().saturating_sub(pattern.len()))
+            .filter(|&start| text[start..].starts_with(pattern))
+            .collect()
+    }
+
+    // Test to check forward searches against a naive search, for patterns
+    // shorter and longer than a word and texts viewed at an offset
+    #[test]
+    fn test_find_forward() {
+        let unit = "ACGTTGCAAGGCTTAACGATCGGATCCA";
+        let text = format!("{}{}{}{}", unit, unit, "TTTTTTTTTT", unit.repeat(3));
+        let dna = PackedDna::from_str(&text).unwrap();
+        for (start, len) in [(0, 1), (2, 5), (5, 28), (20, 32), (1, 33), (3, 60), (0, 84)] {
+            let pattern = &text[start..start + len];
+            let found: Vec<usize> = dna
+                .find_iter(&PackedDna::from_str(pattern).unwrap())
+                .collect();
+            assert_eq!(found, naive(&text, pattern), "pattern {}", pattern);
+            let packed = PackedDna::from_str(pattern).unwrap();
+            assert_eq!(dna.find(&packed), found.first().copied());
+            assert_eq!(dna.rfind(&packed), found.last().copied());
+            // the same search through a view starting mid-word
+            let view = dna.slice(7..);
+            let found: Vec<usize> = view.find_iter(packed.as_slice()).collect();
+            assert_eq!(found, naive(&text[7..], pattern));
+            assert_eq!(view.rfind(packed.as_slice()), found.last().copied());
+        }
+        let runs = PackedDna::from_str("TTT").unwrap();
+        assert_eq!(dna.find_iter(&runs).count(), 8);
+        let absent = PackedDna::from_str("GGGG").unwrap();
+        assert_eq!(dna.find(&absent), None);
+        assert_eq!(dna.rfind(&absent), None);
+        let short = PackedDna::from_str("ACG").unwrap();
+        assert_eq!(short.find(&dna), None);
+        assert_eq!(short.find_iter(&PackedDna::new()).count(), 4);
+    }
+
+    // Test to check that both-strand searches report reverse complement
+    // hits, palindromes on both strands, and agree from either end
+    #[test]
+    fn test_find_both_strands() {
+        let text = "CCGAATTCAAACCTTGGTTTGAGCTT";
+        let dna = PackedDna::from_str(text).unwrap();
+        let both = SearchOptions::new().both_strands(true);
+        let find = |pattern: &str| {
+            let pattern = PackedDna::from_str(pattern).unwrap();
+            both.find_iter(dna.as_slice(), pattern.as_slice())
+                .map(|found| (found.start, found.strand))
+                .collect::<Vec<_>>()
+        };
+        // EcoRI is its own reverse complement
+        assert_eq!(find("GAATTC"), [(2, Strand::Forward), (2, Strand::Reverse)]);
+        // AAGG occurs only as CCTT
+        assert_eq!(find("AAGG"), [(11, Strand::Reverse)]);
+        assert_eq!(find("AAGCTC"), [(20, Strand::Reverse)]);
+        let long = &text[1..];
+        let rc = PackedDna::from_str(long).unwrap().reverse_complement();
+        let found = both.rfind(dna.as_slice(), rc.as_slice()).unwrap();
+        assert_eq!((found.start, found.strand), (1, Strand::Reverse));
+        let palindrome = PackedDna::from_str("GAATTC").unwrap();
+        let last = both.rfind(dna.as_slice(), palindrome.as_slice()).unwrap();
+        assert_eq!((last.start, last.strand), (2, Strand::Reverse));
+    }
+}
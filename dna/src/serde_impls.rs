@@ -0,0 +1,194 @@
+//! `Serialize` and `Deserialize` implementations, behind the `serde`
+//! feature.
+//!
+//! Human-readable formats get nucleotides as the characters `A`, `C`, `G`
+//! and `T` and sequences as strings of them. Binary formats get the two bit
+//! code of a nucleotide as a `u8`, and a sequence as a byte string holding
+//! its length as a little endian `u64` followed by its nucleotides packed
+//! four per byte, first nucleotide in the low bits.
+
+use std::{convert::TryFrom, fmt};
+
+use serde::{
+    de::{self, Deserializer, Visitor},
+    ser::Serializer,
+    Deserialize, Serialize,
+};
+
+use crate::{
+    packed::{words_for, NUCS_PER_WORD},
+    Nuc, PackedDna, ParseOptions,
+};
+
+impl Serialize for Nuc {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_char(char::from(*self))
+        } else {
+            serializer.serialize_u8(self.bits())
+        }
+    }
+}
+
+struct NucVisitor;
+
+impl<'de> Visitor<'de> for NucVisitor {
+    type Value = Nuc;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a nucleotide")
+    }
+
+    fn visit_char<E: de::Error>(self, c: char) -> Result<Nuc, E> {
+        Nuc::try_from(c).map_err(|_| E::invalid_value(de::Unexpected::Char(c), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Nuc, E> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => self.visit_char(c),
+            _ => Err(E::invalid_value(de::Unexpected::Str(s), &self)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, bits: u64) -> Result<Nuc, E> {
+        match u8::try_from(bits) {
+            Ok(bits) if bits < 4 => Ok(Nuc::from_bits(bits)),
+            _ => Err(E::invalid_value(de::Unexpected::Unsigned(bits), &self)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Nuc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Nuc, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_char(NucVisitor)
+        } else {
+            deserializer.deserialize_u8(NucVisitor)
+        }
+    }
+}
+
+impl Serialize for PackedDna {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.collect_str(self);
+        }
+        let packed = self.len() / 4 + usize::from(self.len() & 3 != 0);
+        let mut bytes = Vec::with_capacity(8 + packed);
+        bytes.extend((self.len() as u64).to_le_bytes());
+        bytes.extend(
+            self.words()
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .take(packed),
+        );
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+struct PackedDnaVisitor;
+
+impl<'de> Visitor<'de> for PackedDnaVisitor {
+    type Value = PackedDna;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a DNA sequence")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<PackedDna, E> {
+        ParseOptions::new()
+            .allow_empty(true)
+            .parse(s)
+            .map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<PackedDna, E> {
+        if bytes.len() < 8 {
+            return Err(E::invalid_length(bytes.len(), &self));
+        }
+        let (len, packed) = bytes.split_at(8);
+        let mut word = [0u8; 8];
+        word.copy_from_slice(len);
+        let len = usize::try_from(u64::from_le_bytes(word))
+            .ok()
+            .filter(|&len| len / 4 + usize::from(len & 3 != 0) == packed.len())
+            .ok_or_else(|| E::invalid_length(bytes.len(), &self))?;
+
+        let words: Vec<u64> = packed
+            .chunks(8)
+            .map(|chunk| {
+                let mut word = [0u8; 8];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u64::from_le_bytes(word)
+            })
+            .collect();
+        debug_assert_eq!(words.len(), words_for(len));
+        let tail = len % NUCS_PER_WORD;
+        if let (Some(&last), true) = (words.last(), tail != 0) {
+            if last >> (tail * 2) != 0 {
+                return Err(E::custom("packed DNA has bits set past its end"));
+            }
+        }
+        Ok(PackedDna::from_raw(words, len))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<PackedDna, A::Error> {
+        // formats without a byte string type write the bytes as a sequence
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for PackedDna {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PackedDna, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(PackedDnaVisitor)
+        } else {
+            deserializer.deserialize_bytes(PackedDnaVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Nuc, NucCounts, PackedDna};
+    use std::str::FromStr;
+
+    // Test to check that JSON sees strings and characters, and that both
+    // formats read back what they wrote
+    #[test]
+    fn test_json_and_bincode() {
+        let dna = PackedDna::from_str(&"GATTACA".repeat(10)).unwrap();
+        let json = serde_json::to_string(&dna).unwrap();
+        assert_eq!(json, format!("\"{}\"", "GATTACA".repeat(10)));
+        assert_eq!(serde_json::from_str::<PackedDna>(&json).unwrap(), dna);
+        assert_eq!(serde_json::to_string(&Nuc::G).unwrap(), "\"G\"");
+        assert_eq!(serde_json::from_str::<Nuc>("\"t\"").unwrap(), Nuc::T);
+        assert!(serde_json::from_str::<PackedDna>("\"ACXT\"").is_err());
+        let counts = dna.counts();
+        let json = serde_json::to_string(&counts).unwrap();
+        assert_eq!(json, r#"{"a":30,"c":10,"g":10,"t":20,"n":0}"#);
+        assert_eq!(serde_json::from_str::<NucCounts>(&json).unwrap(), counts);
+
+        for len in [0, 1, 4, 7, 32, 70] {
+            let seq = dna.slice(..len).to_owned();
+            let bytes = bincode::serialize(&seq).unwrap();
+            // bincode's own length prefix, then ours and the packed bytes
+            assert_eq!(bytes.len(), 8 + 8 + len / 4 + usize::from(len & 3 != 0));
+            assert_eq!(bincode::deserialize::<PackedDna>(&bytes).unwrap(), seq);
+        }
+        let bytes = bincode::serialize(&Nuc::T).unwrap();
+        assert_eq!(bytes, [3]);
+        assert_eq!(bincode::deserialize::<Nuc>(&bytes).unwrap(), Nuc::T);
+        assert!(bincode::deserialize::<Nuc>(&[4]).is_err());
+
+        // a length that does not match the packed bytes
+        let mut bytes = bincode::serialize(&dna).unwrap();
+        bytes[8] += 4;
+        assert!(bincode::deserialize::<PackedDna>(&bytes).is_err());
+    }
+}
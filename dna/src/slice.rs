@@ -0,0 +1,334 @@
+//! Borrowed, zero-copy views into a [`PackedDna`].
+
+use std::{
+    fmt::{self, Debug, Display},
+    ops::{Bound, Range, RangeBounds},
+    str,
+};
+
+use crate::{
+    packed::{locate, word_at, words_for, NUCS_PER_WORD},
+    Iter, Nuc, NucCounts, PackedDna,
+};
+
+/// Converts any range over `usize` into a half-open range within `0..len`.
+///
+/// # Panics
+///
+/// Panics if the range is out of bounds or its start is after its end.
+pub(crate) fn resolve_range<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflows usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflows usize"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "slice index starts at {} but ends at {}",
+        start,
+        end
+    );
+    assert!(
+        end <= len,
+        "range end index {} out of range for sequence of length {}",
+        end,
+        len
+    );
+    start..end
+}
+
+/// A borrowed view of a contiguous run of nucleotides inside a
+/// [`PackedDna`], created with [`PackedDna::slice`].
+///
+/// The view shares the packed words of the sequence it was taken from, so
+/// creating one is O(1) no matter how long the range is, and the range may
+/// start at any nucleotide rather than only on a word boundary.
+///
+/// ```
+/// use std::str::FromStr;
+/// use dna::{Nuc, PackedDna};
+///
+/// let chrom = PackedDna::from_str("TTACGGATCCAA").unwrap();
+/// let exon = chrom.slice(2..10);
+/// assert_eq!(exon.len(), 8);
+/// assert_eq!(exon.get(0), Some(Nuc::A));
+/// assert_eq!(exon.to_string(), "ACGGATCC");
+/// assert_eq!(exon.to_owned(), PackedDna::from_str("ACGGATCC").unwrap());
+/// ```
+#[derive(Clone, Copy)]
+pub struct PackedDnaSlice<'a> {
+    // words holding the viewed nucleotides, starting with the word that
+    // holds the first one
+    data: &'a [u64],
+    // offset of the first viewed nucleotide within `data[0]`, always less
+    // than 32
+    start: usize,
+    // number of nucleotides in the view
+    len: usize,
+}
+
+impl<'a> PackedDnaSlice<'a> {
+    /// Creates a view of `len` nucleotides of `data` beginning at
+    /// nucleotide `start`.
+    pub(crate) fn new(data: &'a [u64], start: usize, len: usize) -> PackedDnaSlice<'a> {
+        let first = start / NUCS_PER_WORD;
+        let start = start % NUCS_PER_WORD;
+        let data = &data[first..first + words_for(start + len)];
+        PackedDnaSlice { data, start, len }
+    }
+
+    /// Returns the number of nucleotides in the view.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the view holds no nucleotides.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the nucleotide at `idx` within the view, or `None` if `idx`
+    /// is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<Nuc> {
+        if idx >= self.len {
+            return None;
+        }
+        // SAFETY: `idx` was bounds checked against the length above.
+        Some(unsafe { self.get_unchecked(idx) })
+    }
+
+    /// Returns the nucleotide at `idx` within the view without bounds
+    /// checking.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than [`PackedDnaSlice::len`].
+    pub unsafe fn get_unchecked(&self, idx: usize) -> Nuc {
+        let pos = self.start + idx;
+        let (word, shift) = locate(pos);
+        Nuc::from_bits((*self.data.get_unchecked(word) >> shift) as u8)
+    }
+
+    /// Returns an iterator over the viewed nucleotides.
+    pub fn iter(&self) -> Iter<'a> {
+        Iter::new(self.data, self.start, self.start + self.len)
+    }
+
+    /// Counts the occurrences of each nucleotide in the view.
+    pub fn counts(&self) -> NucCounts {
+        NucCounts::from_words(self.words(), self.len)
+    }
+
+    /// Returns a narrower view of the nucleotides in `range`, relative to
+    /// the start of this view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is after its end.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> PackedDnaSlice<'a> {
+        let range = resolve_range(range, self.len);
+        PackedDnaSlice::new(self.data, self.start + range.start, range.len())
+    }
+
+    /// Copies the viewed nucleotides into a new, owned [`PackedDna`].
+    pub fn to_owned(self) -> PackedDna {
+        PackedDna::from_raw(self.words().collect(), self.len)
+    }
+
+    /// Returns a copy of the viewed nucleotides, complemented.
+    pub fn complement(self) -> PackedDna {
+        self.to_owned().complement()
+    }
+
+    /// Returns a copy of the viewed nucleotides in reverse order.
+    pub fn reverse(self) -> PackedDna {
+        self.to_owned().reverse()
+    }
+
+    /// Returns the reverse complement of the viewed nucleotides.
+    pub fn reverse_complement(self) -> PackedDna {
+        let mut dna = self.to_owned();
+        dna.reverse_complement_in_place();
+        dna
+    }
+
+    /// Returns the 32 nucleotides starting at `pos` within the view packed
+    /// into a word. Slots past the end of the view are unspecified.
+    pub(crate) fn word_at(&self, pos: usize) -> u64 {
+        word_at(self.data, self.start + pos)
+    }
+
+    /// Returns the viewed nucleotides re-aligned to start on a word
+    /// boundary, 32 per word, with the bits past the end of the view
+    /// zeroed.
+    pub(crate) fn words(&self) -> impl Iterator<Item = u64> + 'a {
+        let (data, start, len) = (self.data, self.start, self.len);
+        (0..words_for(len)).map(move |i| {
+            let word = word_at(data, start + i * NUCS_PER_WORD);
+            let remaining = len - i * NUCS_PER_WORD;
+            if remaining < NUCS_PER_WORD {
+                word & ((1u64 << (remaining * 2)) - 1)
+            } else {
+                word
+            }
+        })
+    }
+
+    /// Decodes the viewed nucleotides into upper case ASCII and passes them
+    /// to `write` in chunks of up to one word, stopping at the first error.
+    ///
+    /// Decoding a whole word at a time avoids a writer call per base.
+    pub(crate) fn for_each_ascii<E, F>(&self, mut write: F) -> Result<(), E>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>,
+    {
+        let mut buf = [0u8; NUCS_PER_WORD];
+        let mut remaining = self.len;
+        for word in self.words() {
+            let count = remaining.min(NUCS_PER_WORD);
+            for (slot, byte) in buf[..count].iter_mut().enumerate() {
+                *byte = char::from(Nuc::from_bits((word >> (slot * 2)) as u8)) as u8;
+            }
+            write(&buf[..count])?;
+            remaining -= count;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> From<&'a PackedDna> for PackedDnaSlice<'a> {
+    fn from(dna: &'a PackedDna) -> PackedDnaSlice<'a> {
+        dna.as_slice()
+    }
+}
+
+impl From<PackedDnaSlice<'_>> for PackedDna {
+    fn from(slice: PackedDnaSlice<'_>) -> PackedDna {
+        slice.to_owned()
+    }
+}
+
+/// Formats the view as upper case `A`, `C`, `G` and `T` characters.
+impl Display for PackedDnaSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.for_each_ascii(|chunk| {
+            // the chunks only ever hold ASCII letters
+            f.write_str(str::from_utf8(chunk).expect("ASCII nucleotides"))
+        })
+    }
+}
+
+impl Debug for PackedDnaSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PackedDnaSlice")
+            .field(&format_args!("{}", self))
+            .finish()
+    }
+}
+
+impl PartialEq for PackedDnaSlice<'_> {
+    fn eq(&self, other: &PackedDnaSlice<'_>) -> bool {
+        self.len == other.len && self.words().eq(other.words())
+    }
+}
+
+impl Eq for PackedDnaSlice<'_> {}
+
+impl PartialEq<PackedDna> for PackedDnaSlice<'_> {
+    fn eq(&self, other: &PackedDna) -> bool {
+        *self == other.as_slice()
+    }
+}
+
+impl PartialEq<PackedDnaSlice<'_>> for PackedDna {
+    fn eq(&self, other: &PackedDnaSlice<'_>) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const TEXT: &str = "ACGTTGCAAGCTTACGGATCCATGCAATTGGCCTAGGCATCGATCGTACGTTAGCCGATAGCTAG";
+
+    // Test to check that every possible sub-range, including ones that
+    // start in the middle of a word and span word boundaries, reads back
+    // the same nucleotides as the text it was parsed from
+    #[test]
+    fn test_slice_matches_text() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        for start in 0..=TEXT.len() {
+            for end in start..=TEXT.len() {
+                let slice = dna.slice(start..end);
+                assert_eq!(slice.len(), end - start);
+                assert_eq!(slice.to_string(), TEXT[start..end]);
+                assert_eq!(slice.get(end - start), None);
+                if start < end {
+                    assert_eq!(slice.get(0).map(char::from), TEXT[start..].chars().next());
+                }
+            }
+        }
+    }
+
+    // Test to check that owned copies are identical to parsing the same
+    // sub-range directly, padding bits included
+    #[test]
+    fn test_slice_to_owned() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        for start in [0, 1, 5, 31, 32, 33] {
+            for end in [start, start + 1, 40, 64, TEXT.len()] {
+                let expected = crate::ParseOptions::new()
+                    .allow_empty(true)
+                    .parse(&TEXT[start..end])
+                    .unwrap();
+                let slice = dna.slice(start..end);
+                assert_eq!(slice.to_owned(), expected);
+                assert_eq!(slice, expected);
+                assert_eq!(PackedDna::from(slice), expected);
+            }
+        }
+        assert_eq!(dna.as_slice().to_owned(), dna);
+    }
+
+    // Test to check that slicing a slice is relative to the outer view and
+    // that the different range forms resolve the same way
+    #[test]
+    fn test_slice_nested() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        let outer = dna.slice(30..60);
+        assert_eq!(outer.slice(5..10), dna.slice(35..40));
+        assert_eq!(outer.slice(..), outer);
+        assert_eq!(outer.slice(25..), dna.slice(55..60));
+        assert_eq!(outer.slice(..=2).to_string(), TEXT[30..33]);
+        assert_eq!(dna.slice(3..3), dna.slice(40..40));
+        assert_eq!(format!("{:?}", dna.slice(0..4)), "PackedDnaSlice(ACGT)");
+    }
+
+    // Test to check that reverse complements of unaligned views match
+    // those of owned copies
+    #[test]
+    fn test_slice_reverse_complement() {
+        let dna = PackedDna::from_str(TEXT).unwrap();
+        for (start, end) in [(0, 0), (3, 9), (17, 50), (31, 33), (1, TEXT.len())] {
+            let slice = dna.slice(start..end);
+            let owned = slice.to_owned();
+            assert_eq!(slice.reverse_complement(), owned.reverse_complement());
+            assert_eq!(slice.complement(), owned.complement());
+            assert_eq!(slice.reverse(), owned.reverse());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_slice_out_of_bounds() {
+        let dna = PackedDna::from_str("ACGT").unwrap();
+        dna.slice(2..5);
+    }
+}
@@ -0,0 +1,114 @@
+//! Reading and writing UCSC `.2bit` files.
+//!
+//! A `.2bit` file stores every sequence at two bits per nucleotide, like
+//! [`PackedDna`](crate::PackedDna) does, with the runs of `N` and the
+//! soft-masked regions kept as lists of blocks. An index of the sequence
+//! names at the start of the file lets [`Reader`] load a single sequence
+//! without reading the others.
+//!
+//! ```
+//! use std::{io::Cursor, str::FromStr};
+//! use dna::{twobit::{Reader, Writer}, ParseOptions};
+//!
+//! let chr1 = ParseOptions::new().parse_masked_with("ACgtNNNNacGT", true).unwrap();
+//! let mut writer = Writer::new(Vec::new());
+//! writer.write_masked("chr1", &chr1).unwrap();
+//! let bytes = writer.finish().unwrap();
+//!
+//! let mut reader = Reader::new(Cursor::new(bytes)).unwrap();
+//! assert_eq!(reader.names().collect::<Vec<_>>(), ["chr1"]);
+//! let seq = reader.read_masked("chr1").unwrap();
+//! assert_eq!(seq.to_string(), "ACgtNNNNacGT");
+//! ```
+
+use std::io;
+
+mod reader;
+mod writer;
+
+pub use reader::Reader;
+pub use writer::Writer;
+
+// the signature every file starts with, which also gives its byte order
+const SIGNATURE: u32 = 0x1a41_2743;
+
+// `.2bit` codes nucleotides as T = 0, C = 1, A = 2 and G = 3, first
+// nucleotide in the high bits of a byte; these tables translate a whole
+// byte to and from four of our codes, first nucleotide in the low bits
+const FROM_TWOBIT: [u8; 256] = byte_table(true);
+const TO_TWOBIT: [u8; 256] = byte_table(false);
+
+const fn byte_table(from_twobit: bool) -> [u8; 256] {
+    // our code of every `.2bit` code, and the other way around
+    const FROM: [u8; 4] = [3, 1, 0, 2];
+    const TO: [u8; 4] = [2, 1, 3, 0];
+    let mut table = [0u8; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut converted = 0;
+        let mut slot = 0;
+        while slot < 4 {
+            if from_twobit {
+                let code = (byte >> (6 - 2 * slot)) & 3;
+                converted |= FROM[code] << (2 * slot);
+            } else {
+                let code = (byte >> (2 * slot)) & 3;
+                converted |= TO[code] << (6 - 2 * slot);
+            }
+            slot += 1;
+        }
+        table[byte] = converted;
+        byte += 1;
+    }
+    table
+}
+
+/// An error that can occur while reading a `.2bit` file.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Reading from the underlying source failed.
+    #[error("failed to read 2bit: {0}")]
+    Io(#[from] io::Error),
+    /// The file does not start with the `.2bit` signature.
+    #[error("not a 2bit file")]
+    BadSignature,
+    /// The file is of a version other than 0 or 1.
+    #[error("unsupported 2bit version {0}")]
+    UnsupportedVersion(u32),
+    /// A sequence is not in the file.
+    #[error("no sequence named {name:?}")]
+    UnknownSequence {
+        /// The requested name.
+        name: String,
+    },
+    /// An `N` or mask block of a sequence reaches past its end.
+    #[error("sequence {name:?} has a block past its end")]
+    InvalidBlock {
+        /// The sequence the block belongs to.
+        name: String,
+    },
+    /// A sequence read as a [`PackedDna`](crate::PackedDna) contains `N`.
+    #[error("sequence {name:?} has an unknown base at position {position}")]
+    UnknownBase {
+        /// The sequence.
+        name: String,
+        /// The position of the first `N`, counting from 0.
+        position: usize,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test to check that the byte tables translate codes in both directions
+    // and undo each other
+    #[test]
+    fn test_byte_tables() {
+        // TCAG in `.2bit` is 0b00_01_10_11, and T, C, A, G are 3, 1, 0, 2
+        assert_eq!(FROM_TWOBIT[0b00_01_10_11], 0b10_00_01_11);
+        for byte in 0..=255u8 {
+            assert_eq!(TO_TWOBIT[usize::from(FROM_TWOBIT[usize::from(byte)])], byte);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! A random access `.2bit` reader.
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use super::{Error, FROM_TWOBIT, SIGNATURE};
+use crate::{
+    interval::Intervals,
+    packed::{words_for, NUCS_PER_WORD},
+    MaskedPackedDna, PackedDna,
+};
+
+// bytes read at a time; a multiple of 8 so every chunk holds whole words
+const CHUNK_BYTES: usize = 1 << 16;
+
+/// A reader that loads sequences from a `.2bit` file by name.
+///
+/// Only the header and the index of names are read up front; every
+/// sequence is read when it is asked for, by seeking straight to it. Both
+/// byte orders and both versions of the format, with 32-bit (version 0)
+/// and 64-bit (version 1) offsets, are supported.
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+    big_endian: bool,
+    // names and record offsets in file order, and the position of every
+    // name in that list
+    index: Vec<(String, u64)>,
+    positions: HashMap<String, usize>,
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// Creates a reader over `inner`, reading the header and the index of
+    /// sequence names.
+    pub fn new(mut inner: R) -> Result<Reader<R>, Error> {
+        inner.seek(SeekFrom::Start(0))?;
+        let mut word = [0u8; 4];
+        inner.read_exact(&mut word)?;
+        let big_endian = if u32::from_le_bytes(word) == SIGNATURE {
+            false
+        } else if u32::from_be_bytes(word) == SIGNATURE {
+            true
+        } else {
+            return Err(Error::BadSignature);
+        };
+        let mut reader = Reader {
+            inner,
+            big_endian,
+            index: Vec::new(),
+            positions: HashMap::new(),
+        };
+        let version = reader.read_u32()?;
+        if version > 1 {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let count = reader.read_u32()?;
+        reader.read_u32()?;
+
+        for _ in 0..count {
+            let mut len = [0u8; 1];
+            reader.inner.read_exact(&mut len)?;
+            let mut name = vec![0u8; usize::from(len[0])];
+            reader.inner.read_exact(&mut name)?;
+            let name = String::from_utf8_lossy(&name).into_owned();
+            let offset = if version == 0 {
+                u64::from(reader.read_u32()?)
+            } else {
+                let mut word = [0u8; 8];
+                reader.inner.read_exact(&mut word)?;
+                if big_endian {
+                    u64::from_be_bytes(word)
+                } else {
+                    u64::from_le_bytes(word)
+                }
+            };
+            reader.positions.insert(name.clone(), reader.index.len());
+            reader.index.push((name, offset));
+        }
+        Ok(reader)
+    }
+
+    /// Returns the names of the sequences in file order.
+    pub fn names(&self) -> impl ExactSizeIterator<Item = &str> + '_ {
+        self.index.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns `true` if the file holds a sequence called `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.positions.contains_key(name)
+    }
+
+    /// Returns the length of the sequence called `name`, `N` included,
+    /// without reading the sequence.
+    pub fn seq_len(&mut self, name: &str) -> Result<usize, Error> {
+        self.seek_to(name)?;
+        Ok(self.read_u32()? as usize)
+    }
+
+    /// Reads the sequence called `name` with its `N` runs and soft mask.
+    pub fn read_masked(&mut self, name: &str) -> Result<MaskedPackedDna, Error> {
+        self.seek_to(name)?;
+        let len = self.read_u32()? as usize;
+        let n_runs = self.read_blocks(name, len)?;
+        let soft_mask = self.read_blocks(name, len)?;
+        self.read_u32()?;
+
+        // read a chunk at a time, so a corrupt length fails on truncation
+        // instead of allocating all of it up front
+        let total = len / 4 + usize::from(len & 3 != 0);
+        let mut words: Vec<u64> = Vec::with_capacity(words_for(len).min(CHUNK_BYTES / 8));
+        let mut buf = vec![0u8; total.min(CHUNK_BYTES)];
+        let mut remaining = total;
+        while remaining > 0 {
+            let bytes = &mut buf[..remaining.min(CHUNK_BYTES)];
+            self.inner.read_exact(bytes)?;
+            remaining -= bytes.len();
+            words.extend(bytes.chunks(8).map(|chunk| {
+                let mut word = [0u8; 8];
+                for (to, &from) in word.iter_mut().zip(chunk) {
+                    *to = FROM_TWOBIT[usize::from(from)];
+                }
+                u64::from_le_bytes(word)
+            }));
+        }
+        // the padding of the last byte decodes as nucleotides, so clear it
+        let tail = len % NUCS_PER_WORD;
+        if let (Some(last), true) = (words.last_mut(), tail != 0) {
+            *last &= (1u64 << (tail * 2)) - 1;
+        }
+        debug_assert_eq!(words.len(), words_for(len));
+        let seq = PackedDna::from_raw(words, len);
+        Ok(MaskedPackedDna::from_parts(seq, n_runs, soft_mask))
+    }
+
+    /// Reads the sequence called `name`, dropping its soft mask.
+    ///
+    /// Fails with [`Error::UnknownBase`] if the sequence contains `N`; use
+    /// [`Reader::read_masked`] for those.
+    pub fn read_dna(&mut self, name: &str) -> Result<PackedDna, Error> {
+        let masked = self.read_masked(name)?;
+        PackedDna::try_from(&masked).map_err(|err| Error::UnknownBase {
+            name: name.to_string(),
+            position: err.position,
+        })
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Moves to the record of the sequence called `name`.
+    fn seek_to(&mut self, name: &str) -> Result<(), Error> {
+        let &position = self
+            .positions
+            .get(name)
+            .ok_or_else(|| Error::UnknownSequence {
+                name: name.to_string(),
+            })?;
+        self.inner.seek(SeekFrom::Start(self.index[position].1))?;
+        Ok(())
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        let mut word = [0u8; 4];
+        self.inner.read_exact(&mut word)?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(word)
+        } else {
+            u32::from_le_bytes(word)
+        })
+    }
+
+    /// Reads a block list, its count followed by the starts and then the
+    /// sizes, checking every block lies within `len`.
+    fn read_blocks(&mut self, name: &str, len: usize) -> Result<Intervals, Error> {
+        let count = self.read_u32()? as usize;
+        let invalid = || Error::InvalidBlock {
+            name: name.to_string(),
+        };
+        let total = count.checked_mul(8).ok_or_else(invalid)?;
+        let big_endian = self.big_endian;
+        let mut values: Vec<usize> = Vec::with_capacity((count * 2).min(CHUNK_BYTES / 4));
+        let mut buf = vec![0u8; total.min(CHUNK_BYTES)];
+        let mut remaining = total;
+        while remaining > 0 {
+            let bytes = &mut buf[..remaining.min(CHUNK_BYTES)];
+            self.inner.read_exact(bytes)?;
+            remaining -= bytes.len();
+            values.extend(bytes.chunks(4).map(|chunk| {
+                let word = [chunk[0], chunk[1], chunk[2], chunk[3]];
+                let value = if big_endian {
+                    u32::from_be_bytes(word)
+                } else {
+                    u32::from_le_bytes(word)
+                };
+                value as usize
+            }));
+        }
+        let (starts, sizes) = values.split_at(count);
+        let mut blocks = Vec::with_capacity(starts.len());
+        for (&start, &size) in starts.iter().zip(sizes) {
+            let end = start.checked_add(size).ok_or_else(invalid)?;
+            if end > len {
+                return Err(invalid());
+            }
+            blocks.push(start..end);
+        }
+        Ok(Intervals::from(blocks))
+    }
+}
+
+impl Reader<BufReader<File>> {
+    /// Opens the `.2bit` file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<BufReader<File>>, Error> {
+        Reader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A version 0 file written by hand: "ACGTN" with the N as a block and
+    // "ac" soft-masked, then "TTTT", in big endian byte order
+    fn big_endian_file() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for word in [SIGNATURE, 0, 2, 0] {
+            bytes.extend(word.to_be_bytes());
+        }
+        let first = 16 + (1 + 4 + 4) + (1 + 4 + 4);
+        bytes.extend(b"\x04chr1");
+        bytes.extend((first as u32).to_be_bytes());
+        bytes.extend(b"\x04chr2");
+        bytes.extend((first as u32 + 4 * 8 + 2).to_be_bytes());
+        for word in [5u32, 1, 4, 1, 1, 0, 2, 0] {
+            bytes.extend(word.to_be_bytes());
+        }
+        // A C G T, then the N stored as T and padding
+        bytes.extend([0b10_01_11_00, 0b00_00_00_00]);
+        for word in [4u32, 0, 0, 0] {
+            bytes.extend(word.to_be_bytes());
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    // Test to check reading a hand-written big endian file, including N
+    // blocks, mask blocks and the errors for missing and ambiguous names
+    #[test]
+    fn test_read_big_endian() {
+        let bytes = big_endian_file();
+        let mut reader = Reader::new(std::io::Cursor::new(&bytes)).unwrap();
+        assert_eq!(reader.names().collect::<Vec<_>>(), ["chr1", "chr2"]);
+        assert!(reader.contains("chr2"));
+        assert_eq!(reader.seq_len("chr1").unwrap(), 5);
+        let chr1 = reader.read_masked("chr1").unwrap();
+        assert_eq!(chr1.to_string(), "acGTN");
+        assert_eq!(chr1.as_packed().to_string(), "ACGTA");
+        assert_eq!(reader.read_dna("chr2").unwrap().to_string(), "TTTT");
+        assert!(matches!(
+            reader.read_dna("chr1"),
+            Err(Error::UnknownBase { position: 4, .. })
+        ));
+        assert!(matches!(
+            reader.read_masked("chr3"),
+            Err(Error::UnknownSequence { .. })
+        ));
+        assert!(matches!(
+            Reader::new(std::io::Cursor::new(&bytes[4..])),
+            Err(Error::BadSignature)
+        ));
+
+        // huge lengths and block counts from a corrupt file fail on the
+        // missing data rather than allocating for it
+        let first = 16 + (1 + 4 + 4) + (1 + 4 + 4);
+        let mut corrupt = bytes.clone();
+        corrupt[first + 4..first + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+        let mut reader = Reader::new(std::io::Cursor::new(&corrupt)).unwrap();
+        assert!(matches!(reader.read_masked("chr1"), Err(Error::Io(_))));
+        let mut corrupt = bytes;
+        let second = first + 4 * 8 + 2;
+        corrupt[second..second + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let mut reader = Reader::new(std::io::Cursor::new(&corrupt)).unwrap();
+        assert!(matches!(reader.read_dna("chr2"), Err(Error::Io(_))));
+    }
+}
@@ -0,0 +1,201 @@
+//! A `.2bit` writer.
+
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    io::{self, Write},
+    ops::Range,
+};
+
+use super::{SIGNATURE, TO_TWOBIT};
+use crate::{MaskedPackedDna, PackedDnaSlice};
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Writes sequences to a `.2bit` file.
+///
+/// The index of names at the start of the file needs the size of every
+/// sequence, so sequences are packed into memory as they are added and the
+/// whole file is written by [`Writer::finish`]. Files are written in little
+/// endian byte order, as version 0 unless the offsets need 64 bits or
+/// [`Writer::long_offsets`] asks for version 1.
+#[derive(Debug)]
+pub struct Writer<W> {
+    inner: W,
+    // names and encoded records in the order they were added
+    records: Vec<(String, Vec<u8>)>,
+    names: HashSet<String>,
+    long_offsets: bool,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a writer over `inner`.
+    pub fn new(inner: W) -> Writer<W> {
+        Writer {
+            inner,
+            records: Vec::new(),
+            names: HashSet::new(),
+            long_offsets: false,
+        }
+    }
+
+    /// When enabled, the file is always written as version 1 with 64-bit
+    /// offsets, even if it is small enough for version 0.
+    pub fn long_offsets(mut self, yes: bool) -> Writer<W> {
+        self.long_offsets = yes;
+        self
+    }
+
+    /// Adds a sequence with its `N` runs and soft mask.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if the name is empty,
+    /// longer than 255 bytes or already used, or the sequence is too long
+    /// for the format.
+    pub fn write_masked(&mut self, name: &str, seq: &MaskedPackedDna) -> io::Result<()> {
+        self.add(
+            name,
+            seq.as_packed().as_slice(),
+            seq.n_runs(),
+            seq.masked_intervals(),
+        )
+    }
+
+    /// Adds a sequence without `N` runs or soft mask, failing the same way
+    /// as [`Writer::write_masked`].
+    pub fn write_seq(&mut self, name: &str, seq: PackedDnaSlice<'_>) -> io::Result<()> {
+        self.add(name, seq, &[], &[])
+    }
+
+    /// Writes the header, the index and every sequence added, and returns
+    /// the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let records = &self.records;
+        let index_len = |offset_len: usize| {
+            records
+                .iter()
+                .map(|(name, _)| 1 + name.len() + offset_len)
+                .sum::<usize>()
+        };
+        let data_len: usize = self.records.iter().map(|(_, record)| record.len()).sum();
+        let long = self.long_offsets || u32::try_from(16 + index_len(4) + data_len).is_err();
+        let count = u32::try_from(self.records.len())
+            .map_err(|_| invalid_input("too many sequences".to_string()))?;
+
+        for word in [SIGNATURE, u32::from(long), count, 0] {
+            self.inner.write_all(&word.to_le_bytes())?;
+        }
+        let mut offset = (16 + index_len(if long { 8 } else { 4 })) as u64;
+        for (name, record) in records {
+            self.inner.write_all(&[name.len() as u8])?;
+            self.inner.write_all(name.as_bytes())?;
+            if long {
+                self.inner.write_all(&offset.to_le_bytes())?;
+            } else {
+                self.inner.write_all(&(offset as u32).to_le_bytes())?;
+            }
+            offset += record.len() as u64;
+        }
+        for (_, record) in records {
+            self.inner.write_all(record)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Encodes a sequence record and queues it for writing.
+    fn add(
+        &mut self,
+        name: &str,
+        seq: PackedDnaSlice<'_>,
+        n_runs: &[Range<usize>],
+        soft_mask: &[Range<usize>],
+    ) -> io::Result<()> {
+        if name.is_empty() || name.len() > 255 {
+            return Err(invalid_input(format!(
+                "sequence name {:?} must be 1 to 255 bytes long",
+                name
+            )));
+        }
+        if self.names.contains(name) {
+            return Err(invalid_input(format!("duplicate sequence name {:?}", name)));
+        }
+        let len = u32::try_from(seq.len())
+            .map_err(|_| invalid_input(format!("sequence {:?} is too long", name)))?;
+
+        let mut record = Vec::with_capacity(16 + (n_runs.len() + soft_mask.len()) * 8);
+        record.extend(len.to_le_bytes());
+        for blocks in [n_runs, soft_mask] {
+            // every block lies within the sequence, so fits in 32 bits
+            record.extend((blocks.len() as u32).to_le_bytes());
+            for block in blocks {
+                record.extend((block.start as u32).to_le_bytes());
+            }
+            for block in blocks {
+                record.extend((block.len() as u32).to_le_bytes());
+            }
+        }
+        record.extend(0u32.to_le_bytes());
+
+        let bytes = seq.len() / 4 + usize::from(seq.len() & 3 != 0);
+        record.extend(
+            seq.words()
+                .flat_map(|word| word.to_le_bytes())
+                .map(|byte| TO_TWOBIT[usize::from(byte)])
+                .take(bytes),
+        );
+        // pad the last byte with zeros, as UCSC tools do
+        let tail = seq.len() & 3;
+        if tail != 0 {
+            *record.last_mut().expect("a partial byte") &= 0xff << (8 - 2 * tail);
+        }
+
+        self.names.insert(name.to_string());
+        self.records.push((name.to_string(), record));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{twobit::Reader, PackedDna, ParseOptions};
+    use std::{io::Cursor, str::FromStr};
+
+    // Test to check that sequences of every length modulo four, with N runs
+    // and soft masks, read back the same in both versions
+    #[test]
+    fn test_write_round_trip() {
+        let masked = ParseOptions::new()
+            .parse_masked_with(&format!("NNacgt{}GGnnnnTTTTAC", "ACGTTGCA".repeat(9)), true)
+            .unwrap();
+        let plain = PackedDna::from_str("ACGTTGCAAC").unwrap();
+        for &long in &[false, true] {
+            let mut writer = Writer::new(Vec::new()).long_offsets(long);
+            for len in 0..8 {
+                writer
+                    .write_seq(&format!("plain{}", len), plain.slice(..len))
+                    .unwrap();
+            }
+            writer.write_masked("masked", &masked).unwrap();
+            let bytes = writer.finish().unwrap();
+            assert_eq!(bytes[4], u8::from(long));
+
+            let mut reader = Reader::new(Cursor::new(bytes)).unwrap();
+            assert_eq!(reader.names().len(), 9);
+            for len in 0..8 {
+                let seq = reader.read_dna(&format!("plain{}", len)).unwrap();
+                assert_eq!(seq, plain.slice(..len).to_owned());
+            }
+            assert_eq!(reader.read_masked("masked").unwrap(), masked);
+        }
+
+        let mut writer = Writer::new(Vec::new());
+        writer.write_seq("a", plain.as_slice()).unwrap();
+        let err = writer.write_seq("a", plain.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = writer.write_seq("", plain.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -1,29 +1,27 @@
-// TODO: implement a nucleotide counter
-//
-// command line argument parsing has been provided
-// you must use the PackedDna struct you previously implemented
-// if there is any functionality you would like to add to PackedDna feel free to do so in the DNA
-// crate
-//
-// If run with `nuccount --dna ACGTTT" it should print the following to stdout:
-// ```
-// Input: ACGTTT
-//
-// A: 1
-// C: 1
-// G: 1
-// T: 3
-// ```
-//
-// be sure to exit with informative error messages if the input is invalid
-
-use dna::PackedDna;
-use std::str::FromStr;
+//! `nuccount` counts the occurrences of each nucleotide in DNA.
+//!
+//! With `--dna ACGTTT` it counts the given sequence, echoing it back first:
+//!
+//! ```text
+//! Input: ACGTTT
+//!
+//! A: 1
+//! C: 1
+//! G: 1
+//! T: 3
+//! ```
+//!
+//! With `--file genome.fa` it sums the counts over every record of a FASTA
+//! file, which may be gzip or bgzip compressed, and also prints the number
+//! of `N` positions in assembly gaps. Invalid input is reported on stderr
+//! with a non-zero exit status.
+
+use dna::{fasta, Nuc, NucCounts, ParseDnaError, ParseOptions};
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
 use structopt::StructOpt;
-// These need to be imported if need to use from_iter construct function
-// of PackedDNA struct
-// use dna::Nuc;
-// use std::iter::FromIterator;
 
 /// Count the number of occurrences of each nucleotide in the provided DNA.
 #[derive(Debug, StructOpt)]
@@ -31,21 +29,79 @@ struct Opts {
     /// The DNA sequence for which we should retrieve a nucleotide count.
     ///
     /// It is case insensitive but only nucleotides A, C, G and T are supported.
-    #[structopt(short = "d", long, required = true)]
-    dna: String,
+    #[structopt(short = "d", long, required_unless = "file")]
+    dna: Option<String>,
+
+    /// A FASTA file whose nucleotides should be counted instead, summed over
+    /// all of its records.
+    ///
+    /// gzip and bgzip compressed files are decompressed automatically.
+    #[structopt(short = "f", long, parse(from_os_str), conflicts_with = "dna")]
+    file: Option<PathBuf>,
 }
 
 fn main() {
     let opts = Opts::from_args();
-    let dna1 = opts.dna;
+    if let Some(path) = opts.file {
+        count_file(&path);
+        return;
+    }
+    let dna1 = opts.dna.expect("--dna is required without --file");
     println!("Input: {}", &dna1);
-    // let nu_data = vec![Nuc::A, Nuc::C, Nuc::G, Nuc::T, Nuc::T, Nuc::T, Nuc::G];
-    // let c = PackedDna::from_iter(vec![]);
-    // c.print_data();
+    println!();
 
-    // calling the from str constructor from DNA crate to build the
-    // PackedDNA struct based on input strings
-    let d = PackedDna::from_str(&dna1);
+    // calling the parser from DNA crate to build the PackedDNA struct based
+    // on input strings, reporting every invalid character at once
+    let d = match ParseOptions::new().collect_all(true).parse(&dna1) {
+        Ok(d) => d,
+        Err(ParseDnaError::InvalidSymbols(symbols)) => {
+            for (offset, symbol) in symbols {
+                eprintln!("Error: invalid nucleotide {:?} at byte {}", symbol, offset);
+            }
+            eprintln!("Please rerun using only {{A,C,G,T}}");
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    };
     // prints the frequencies of the nucleotides present in the input string
-    d.expect("REASON").print_data();
+    print_counts(&d.counts());
+}
+
+/// Prints the nucleotide counts of every record in the FASTA file at `path`
+/// added together, followed by the number of `N` positions.
+fn count_file(path: &Path) {
+    println!("Input: {}", path.display());
+    println!();
+
+    // reference assemblies hold runs of N, so records are read masked and
+    // the N positions counted separately
+    let mut reader = match fasta::Reader::from_path(path) {
+        Ok(reader) => reader.soft_mask(false),
+        Err(err) => {
+            eprintln!("Error: cannot open {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    };
+    let mut counts = NucCounts::new();
+    while let Some(record) = reader.next_masked() {
+        match record {
+            Ok(record) => counts += record.seq.counts(),
+            Err(err) => {
+                eprintln!("Error: {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        }
+    }
+    print_counts(&counts);
+    println!("N: {}", counts.n());
+}
+
+/// Prints one `<nucleotide>: <count>` line per nucleotide.
+fn print_counts(counts: &NucCounts) {
+    for nuc in [Nuc::A, Nuc::C, Nuc::G, Nuc::T] {
+        println!("{}: {}", nuc, counts[nuc]);
+    }
 }